@@ -5,20 +5,27 @@ use wasm_bindgen::closure::{WasmClosure, WasmClosureFnOnce};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{
-    CanvasRenderingContext2d, Document, Element, HtmlCanvasElement, HtmlElement, HtmlImageElement,
-    Response, Window,
+    CanvasRenderingContext2d, Document, Element, Headers, HtmlCanvasElement, HtmlElement,
+    HtmlImageElement, RequestInit, Response, Storage, UrlSearchParams, Window,
 };
 
+// Outside the browser (native unit tests) there is no console, so print instead
 macro_rules!log {
-  ($($t:tt)*) => {
+  ($($t:tt)*) => {{
+    #[cfg(target_arch = "wasm32")]
     web_sys::console::log_1(&format!($($t)*).into());
-  }
+    #[cfg(not(target_arch = "wasm32"))]
+    println!($($t)*);
+  }}
 }
 
 macro_rules!error {
-  ($($t:tt)*) => {
+  ($($t:tt)*) => {{
+    #[cfg(target_arch = "wasm32")]
     web_sys::console::error_1(&format!($($t)*).into());
-  }
+    #[cfg(not(target_arch = "wasm32"))]
+    eprintln!($($t)*);
+  }}
 }
 
 pub fn canvas() -> Result<HtmlCanvasElement> {
@@ -92,6 +99,35 @@ pub async fn fetch_json(json_path: &str) -> Result<JsValue> {
     .map_err(|err| anyhow!("error fetching JSON {:#?}", err))
 }
 
+/// POST `body` as JSON to `url`. Fails unless the response status is 2xx.
+pub async fn fetch_post_json(url: &str, body: &str) -> Result<Response> {
+    let headers =
+        Headers::new().map_err(|err| anyhow!("Could not create request headers {:#?}", err))?;
+    headers
+        .set("Content-Type", "application/json")
+        .map_err(|err| anyhow!("Could not set the content type {:#?}", err))?;
+    let init = RequestInit::new();
+    init.set_method("POST");
+    init.set_headers(&headers);
+    init.set_body(&JsValue::from_str(body));
+
+    let response: Response = JsFuture::from(window()?.fetch_with_str_and_init(url, &init))
+        .await
+        .map_err(|err| anyhow!("error posting to {url} {:#?}", err))?
+        .dyn_into()
+        .map_err(|element| anyhow!("Error converting {:#?} to Response", element))?;
+
+    if response.ok() {
+        Ok(response)
+    } else {
+        Err(anyhow!(
+            "{url} responded {} {}",
+            response.status(),
+            response.status_text()
+        ))
+    }
+}
+
 pub async fn fetch_response(json_path: &str) -> Result<Response> {
     fetch_with_str(json_path)
         .await?
@@ -134,6 +170,13 @@ pub fn hide_ui() -> Result<()> {
     }
 }
 
+pub fn local_storage() -> Result<Storage> {
+    window()?
+        .local_storage()
+        .map_err(|err| anyhow!("Error accessing local storage {:#?}", err))?
+        .ok_or_else(|| anyhow!("No Local Storage Found"))
+}
+
 pub fn new_image() -> Result<HtmlImageElement> {
     HtmlImageElement::new().map_err(|err| anyhow!("Could not create HtmlImageElement {:#?}", err))
 }
@@ -145,6 +188,18 @@ pub fn now() -> Result<f64> {
         .now())
 }
 
+/// Value of `name` in the page URL's query string, if present
+pub fn query_param(name: &str) -> Result<Option<String>> {
+    let search = window()?
+        .location()
+        .search()
+        .map_err(|err| anyhow!("Could not read the URL query string {:#?}", err))?;
+
+    UrlSearchParams::new_with_str(&search)
+        .map(|params| params.get(name))
+        .map_err(|err| anyhow!("Could not parse the URL query string {:#?}", err))
+}
+
 pub type LoopClosure = Closure<dyn FnMut(f64)>;
 
 pub fn request_animation_frame(callback: &LoopClosure) -> Result<i32> {
@@ -160,6 +215,13 @@ where
     wasm_bindgen_futures::spawn_local(future)
 }
 
+/// Current UTC date as YYYY-MM-DD
+pub fn today() -> String {
+    let iso_date: String = js_sys::Date::new_0().to_iso_string().into();
+
+    iso_date.chars().take(10).collect()
+}
+
 pub fn window() -> Result<Window> {
     web_sys::window().ok_or_else(|| anyhow!("No Window Found"))
 }
@@ -1,10 +1,15 @@
 pub mod audio;
+pub mod broadphase;
 pub mod image;
 pub mod input;
+pub mod physics;
 pub mod rect;
 pub mod renderer;
+pub mod sfx;
 pub mod sheet;
 pub mod sprite_sheet;
+pub mod storage;
+pub mod synth;
 
 use crate::browser::{self, LoopClosure};
 use anyhow::{anyhow, Result};
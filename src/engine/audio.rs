@@ -1,4 +1,5 @@
-use anyhow::Result;
+use super::synth::{self, Tone};
+use anyhow::{anyhow, Result};
 use web_sys::{AudioBuffer, AudioContext};
 
 use crate::{
@@ -18,6 +19,11 @@ impl Audio {
         })
     }
 
+    /// Seconds elapsed on the audio clock
+    pub fn current_time(&self) -> f64 {
+        self.context.current_time()
+    }
+
     pub async fn load_sound(&self, filename: &str) -> Result<Sound> {
         let array_buffer = browser::fetch_array_buffer(filename).await?;
         let buffer = sound::decode_audio_data(&self.context, array_buffer).await?;
@@ -25,12 +31,70 @@ impl Audio {
         Ok(Sound { buffer })
     }
 
+    /// Load the first of `filenames` that can be fetched and decoded (e.g. the
+    /// same sound in several formats, preferred first)
+    pub async fn load_first_sound(&self, filenames: &[&str]) -> Result<Sound> {
+        let mut errors = Vec::new();
+        for filename in filenames {
+            match self.load_sound(filename).await {
+                Ok(sound) => return Ok(sound),
+                Err(err) => errors.push(format!("{filename}: {err}")),
+            }
+        }
+
+        Err(anyhow!(
+            "Could not load any of {:?}\n{}",
+            filenames,
+            errors.join("\n")
+        ))
+    }
+
+    /// Load the first playable of `filenames`, or else synthesize `fallback`,
+    /// or else return silence, so a missing or undecodable file never stops
+    /// the game from starting.
+    pub async fn load_sound_or_fallback(
+        &self,
+        filenames: &[&str],
+        fallback: &Tone,
+    ) -> Result<Sound> {
+        match self.load_first_sound(filenames).await {
+            Ok(sound) => Ok(sound),
+            Err(err) => {
+                error!("{:#?}\nUsing synthesized fallback", err);
+                self.synthesize(fallback).or_else(|err| {
+                    error!("Could not synthesize fallback {:#?}\nUsing silence", err);
+                    self.synthesize(&Tone::SILENCE)
+                })
+            }
+        }
+    }
+
+    pub fn synthesize(&self, tone: &Tone) -> Result<Sound> {
+        self.synthesize_sequence(std::slice::from_ref(tone))
+    }
+
+    /// One sound of `tones` played one after the other
+    pub fn synthesize_sequence(&self, tones: &[Tone]) -> Result<Sound> {
+        let samples = synth::sequence_samples(tones, self.context.sample_rate());
+        let buffer = sound::create_buffer(&self.context, &samples)?;
+
+        Ok(Sound { buffer })
+    }
+
     pub fn play_looping_sound(&self, sound: &Sound) -> Result<()> {
         sound::play_sound(&self.context, &sound.buffer, Looping::Yes)
     }
 
-    pub fn play_sound(&self, sound: &Sound) -> Result<()> {
-        sound::play_sound(&self.context, &sound.buffer, Looping::No)
+    /// Play a one-shot sound. Use options to alter pitch, volume and stereo position.
+    pub fn play_sound(&self, sound: &Sound, options: PlaybackOptions) -> Result<()> {
+        sound::play_sound_ext(
+            &self.context,
+            &sound.buffer,
+            Looping::No,
+            options.playback_rate,
+            options.volume,
+            options.pan,
+        )
     }
 }
 
@@ -38,3 +102,60 @@ impl Audio {
 pub struct Sound {
     pub buffer: AudioBuffer,
 }
+
+impl Sound {
+    /// Length in seconds when played at normal speed
+    pub fn duration(&self) -> f64 {
+        self.buffer.duration()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlaybackOptions {
+    /// Stereo position. -1.0 is full left, 0.0 centered and 1.0 full right
+    pub pan: f32,
+    /// 1.0 is normal speed. Higher values raise the pitch, lower values drop it
+    pub playback_rate: f32,
+    /// 1.0 is the sound's own level
+    pub volume: f32,
+}
+
+impl Default for PlaybackOptions {
+    fn default() -> Self {
+        PlaybackOptions {
+            pan: 0.0,
+            playback_rate: 1.0,
+            volume: 1.0,
+        }
+    }
+}
+
+/// Stereo pan for an emitter at horizontal position `x` on a screen `width`
+/// wide: full left at the left edge, full right at the right edge and clamped
+/// for emitters off screen
+pub fn pan_for(x: i16, width: i16) -> f32 {
+    if width <= 0 {
+        return 0.0;
+    }
+
+    (2.0 * f32::from(x) / f32::from(width) - 1.0).clamp(-1.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pan_follows_position_across_the_screen() {
+        assert_eq!(pan_for(0, 600), -1.0);
+        assert_eq!(pan_for(300, 600), 0.0);
+        assert_eq!(pan_for(600, 600), 1.0);
+        assert_eq!(pan_for(450, 600), 0.5);
+    }
+
+    #[test]
+    fn pan_is_clamped_off_screen() {
+        assert_eq!(pan_for(-200, 600), -1.0);
+        assert_eq!(pan_for(1200, 600), 1.0);
+    }
+}
@@ -0,0 +1,178 @@
+/// Broadphase for things that scroll along x together, e.g. a course's
+/// obstacles: a sorted sweep on x. Items are kept in order of their left
+/// edge along the course, so finding those near a span only looks at the
+/// items that could overlap it, and scrolling doesn't reorder anything.
+pub struct SweepIndex<T> {
+    entries: Vec<Entry<T>>,
+    /// How far everything has scrolled since the index was made, so that
+    /// screen x = course x + `scrolled`
+    scrolled: i32,
+    /// Widest item, which bounds how far left of a span an overlapping item
+    /// can start
+    widest: i32,
+}
+
+struct Entry<T> {
+    item: T,
+    left: i32,
+    right: i32,
+}
+
+impl<T> Default for SweepIndex<T> {
+    fn default() -> Self {
+        SweepIndex {
+            entries: vec![],
+            scrolled: 0,
+            widest: 0,
+        }
+    }
+}
+
+impl<T> SweepIndex<T> {
+    /// Every item, from left to right
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.entries.iter().map(|entry| &entry.item)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Items overlapping `left` to `right` on the screen, from left to right
+    pub fn near(&self, left: i16, right: i16) -> impl Iterator<Item = &T> {
+        let left = i32::from(left) - self.scrolled;
+        let right = i32::from(right) - self.scrolled;
+        let first = self
+            .entries
+            .partition_point(|entry| entry.left < left - self.widest);
+        let end = self.entries.partition_point(|entry| entry.left <= right);
+
+        self.entries[first..end.max(first)]
+            .iter()
+            .filter(move |entry| entry.right >= left)
+            .map(|entry| &entry.item)
+    }
+
+    /// Drop the items that end at or left of `x` on the screen. Returns how
+    /// many were dropped.
+    pub fn remove_left_of(&mut self, x: i16) -> usize {
+        let x = i32::from(x) - self.scrolled;
+        let candidates = self.entries.partition_point(|entry| entry.left <= x);
+        if !self.entries[..candidates]
+            .iter()
+            .any(|entry| entry.right <= x)
+        {
+            return 0;
+        }
+
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.right > x);
+        self.widest = self
+            .entries
+            .iter()
+            .map(|entry| entry.right - entry.left)
+            .max()
+            .unwrap_or(0);
+
+        before - self.entries.len()
+    }
+
+    /// Move every item by `x`
+    pub fn scroll(&mut self, x: i16) {
+        self.scrolled += i32::from(x);
+    }
+
+    fn entry(&self, item: T, left: i16, right: i16) -> Entry<T> {
+        Entry {
+            item,
+            left: i32::from(left) - self.scrolled,
+            right: i32::from(right) - self.scrolled,
+        }
+    }
+}
+
+impl<T> Extend<(T, i16, i16)> for SweepIndex<T> {
+    fn extend<I: IntoIterator<Item = (T, i16, i16)>>(&mut self, items: I) {
+        for (item, left, right) in items {
+            let entry = self.entry(item, left, right);
+            self.widest = self.widest.max(entry.right - entry.left);
+            self.entries.push(entry);
+        }
+        self.entries.sort_by_key(|entry| entry.left);
+    }
+}
+
+impl<T> FromIterator<(T, i16, i16)> for SweepIndex<T> {
+    fn from_iter<I: IntoIterator<Item = (T, i16, i16)>>(items: I) -> Self {
+        let mut index = SweepIndex::default();
+        index.extend(items);
+
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn course() -> SweepIndex<&'static str> {
+        [
+            ("platform", 100, 484),
+            ("stone", 600, 690),
+            ("left mark", 20, 21),
+            ("far stone", 2000, 2090),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn finds_only_the_items_overlapping_a_span() {
+        let index = course();
+
+        assert_eq!(
+            index.near(450, 620).collect::<Vec<_>>(),
+            vec![&"platform", &"stone"]
+        );
+        assert_eq!(index.near(0, 50).collect::<Vec<_>>(), vec![&"left mark"]);
+        assert_eq!(index.near(700, 1900).count(), 0);
+    }
+
+    #[test]
+    fn keeps_items_in_place_as_everything_scrolls() {
+        let mut index = course();
+
+        for _ in 0..10_000 {
+            index.scroll(-6);
+        }
+        index.extend([("new stone", 300, 390)]);
+        index.scroll(-100);
+
+        assert_eq!(index.near(200, 250).collect::<Vec<_>>(), vec![&"new stone"]);
+        assert_eq!(
+            index.iter().collect::<Vec<_>>(),
+            vec![
+                &"left mark",
+                &"platform",
+                &"stone",
+                &"far stone",
+                &"new stone"
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_items_scrolled_past() {
+        let mut index = course();
+
+        index.scroll(-500);
+        assert_eq!(index.remove_left_of(0), 2);
+        assert_eq!(index.remove_left_of(0), 0);
+
+        assert_eq!(
+            index.iter().collect::<Vec<_>>(),
+            vec![&"stone", &"far stone"]
+        );
+        assert_eq!(index.near(0, 100).collect::<Vec<_>>(), vec![&"stone"]);
+    }
+}
@@ -0,0 +1,329 @@
+use super::rect::{Point, Rect};
+
+/// Vertical speed gained each frame by a body with a gravity scale of 1
+pub const GRAVITY: i16 = 1;
+
+/// What a body is standing on
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Contact {
+    Airborne,
+    Ground,
+    Platform,
+}
+
+/// A change in what a body is standing on
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContactChange {
+    Landed(Contact),
+    Left(Contact),
+}
+
+/// Something that falls under gravity and comes to rest on the ground or on
+/// a platform. `position` is the top left of the body.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct KinematicBody {
+    pub gravity_scale: i16,
+    pub position: Point,
+    pub terminal_velocity: i16,
+    pub velocity: Point,
+    contact: Contact,
+    /// How far the last step moved the body
+    displacement: Point,
+    /// Lowest `position.y` the body falls to
+    floor: i16,
+    /// `position.y` when standing on the ground
+    ground: i16,
+    height: i16,
+    /// Whether a platform held the body up since its last step
+    held: bool,
+}
+
+impl KinematicBody {
+    /// A body `height` tall in a world whose ground is at `ground`
+    pub fn new(position: Point, height: i16, ground: i16, terminal_velocity: i16) -> Self {
+        let ground = ground - height;
+
+        KinematicBody {
+            gravity_scale: 1,
+            position,
+            terminal_velocity,
+            velocity: Point::default(),
+            contact: if position.y == ground {
+                Contact::Ground
+            } else {
+                Contact::Airborne
+            },
+            displacement: Point::default(),
+            floor: ground,
+            ground,
+            height,
+            held: false,
+        }
+    }
+
+    pub fn contact(&self) -> Contact {
+        self.contact
+    }
+
+    /// How far the last step moved the body, not counting where it was put
+    /// since
+    pub fn displacement(&self) -> Point {
+        self.displacement
+    }
+
+    pub fn floor(&self) -> i16 {
+        self.floor
+    }
+
+    /// Put the body on a platform with its top at `top`. The platform has to
+    /// keep doing this every step or the body leaves it on the next one.
+    pub fn rest_on(&mut self, top: i16) {
+        self.position.y = top - self.height;
+        self.contact = Contact::Platform;
+        self.held = true;
+    }
+
+    /// Let the body fall no further than `bottom`, the top of a platform or
+    /// the ground, until the floor is set again
+    pub fn set_floor(&mut self, bottom: i16) {
+        self.floor = bottom - self.height;
+    }
+
+    /// Apply one frame of gravity and movement. Returns the change in contact,
+    /// if any.
+    pub fn step(&mut self) -> Option<ContactChange> {
+        self.velocity.y =
+            (self.velocity.y + GRAVITY * self.gravity_scale).min(self.terminal_velocity);
+        let before = self.position.y;
+        self.position.y = (self.position.y + self.velocity.y).min(self.floor);
+        self.displacement = Point {
+            x: 0,
+            y: self.position.y - before,
+        };
+
+        let contact = if self.position.y == self.floor && self.floor == self.ground {
+            Contact::Ground
+        } else if self.position.y == self.floor
+            || (self.contact == Contact::Platform && self.held && self.velocity.y >= 0)
+        {
+            Contact::Platform
+        } else {
+            Contact::Airborne
+        };
+        self.held = false;
+
+        let before = std::mem::replace(&mut self.contact, contact);
+        match (before, contact) {
+            (before, after) if before == after => None,
+            (before, Contact::Airborne) => Some(ContactChange::Left(before)),
+            (_, after) => Some(ContactChange::Landed(after)),
+        }
+    }
+}
+
+/// Where a box moving by `motion` first touches `target`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sweep {
+    /// Out of the face of `target` that was touched, e.g. up for its top
+    pub normal: Point,
+    /// Fraction of `motion` done when they touch. 0 if they already did.
+    pub time: f32,
+}
+
+impl Sweep {
+    pub fn on_top(&self) -> bool {
+        self.normal.y < 0
+    }
+}
+
+/// Swept AABB test of the box `from` moving by `motion` against a still
+/// `target`, so nothing thinner than a frame's movement is passed through.
+/// Boxes that already overlap touch at the face the box is least far
+/// through, unless it is moving back out of it.
+pub fn sweep(from: &Rect, motion: Point, target: &Rect) -> Option<Sweep> {
+    let (entry_x, exit_x) = overlap_times(
+        (from.left(), from.right()),
+        motion.x,
+        (target.left(), target.right()),
+    )?;
+    let (entry_y, exit_y) = overlap_times(
+        (from.top(), from.bottom()),
+        motion.y,
+        (target.top(), target.bottom()),
+    )?;
+    let entry = entry_x.max(entry_y);
+    if entry > exit_x.min(exit_y) || entry > 1.0 || exit_x.min(exit_y) < 0.0 {
+        return None;
+    }
+
+    if entry > 0.0 {
+        let normal = if entry_x > entry_y {
+            Point {
+                x: -motion.x.signum(),
+                y: 0,
+            }
+        } else {
+            Point {
+                x: 0,
+                y: -motion.y.signum(),
+            }
+        };
+        return Some(Sweep {
+            normal,
+            time: entry,
+        });
+    }
+
+    [
+        (Point { x: -1, y: 0 }, from.right() - target.left()),
+        (Point { x: 1, y: 0 }, target.right() - from.left()),
+        (Point { x: 0, y: -1 }, from.bottom() - target.top()),
+        (Point { x: 0, y: 1 }, target.bottom() - from.top()),
+    ]
+    .into_iter()
+    .min_by_key(|(_, depth)| *depth)
+    .filter(|(normal, _)| normal.x * motion.x + normal.y * motion.y <= 0)
+    .map(|(normal, _)| Sweep { normal, time: 0.0 })
+}
+
+/// Fractions of `distance` at which the span `from` moving by it starts and
+/// stops overlapping `target`, on one axis. Unbounded if it doesn't move and
+/// overlaps, None if it doesn't move and never does.
+fn overlap_times(from: (i16, i16), distance: i16, target: (i16, i16)) -> Option<(f32, f32)> {
+    let ((start, end), (target_start, target_end)) = (from, target);
+    if distance == 0 {
+        return (start < target_end && end >= target_start)
+            .then_some((f32::NEG_INFINITY, f32::INFINITY));
+    }
+
+    let (entry, exit) = if distance > 0 {
+        (target_start - end, target_end - start)
+    } else {
+        (target_end - start, target_start - end)
+    };
+
+    Some((
+        entry as f32 / distance as f32,
+        exit as f32 / distance as f32,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GROUND: i16 = 600;
+    const HEIGHT: i16 = 100;
+
+    fn standing() -> KinematicBody {
+        KinematicBody::new(
+            Point {
+                x: 0,
+                y: GROUND - HEIGHT,
+            },
+            HEIGHT,
+            GROUND,
+            20,
+        )
+    }
+
+    #[test]
+    fn jumps_off_the_ground_and_lands_back_on_it() {
+        let mut body = standing();
+        body.velocity.y = -10;
+
+        assert_eq!(body.step(), Some(ContactChange::Left(Contact::Ground)));
+        let changes: Vec<ContactChange> = (0..30).filter_map(|_| body.step()).collect();
+
+        assert_eq!(changes, vec![ContactChange::Landed(Contact::Ground)]);
+        assert_eq!(body.position.y, GROUND - HEIGHT);
+    }
+
+    #[test]
+    fn falls_no_faster_than_terminal_velocity() {
+        let mut body = standing();
+        body.gravity_scale = 3;
+        body.position.y = -1000;
+
+        for _ in 0..20 {
+            body.step();
+        }
+
+        assert_eq!(body.velocity.y, 20);
+        assert_eq!(body.contact(), Contact::Airborne);
+    }
+
+    #[test]
+    fn stays_on_a_platform_only_while_it_holds_the_body_up() {
+        let mut body = standing();
+        body.rest_on(400);
+
+        assert_eq!(body.step(), None);
+        body.rest_on(400);
+        assert_eq!(body.step(), None);
+        assert_eq!(body.contact(), Contact::Platform);
+        assert_eq!(body.step(), Some(ContactChange::Left(Contact::Platform)));
+    }
+
+    #[test]
+    fn a_fast_box_touches_a_thin_one_it_would_pass_between_frames() {
+        let from = Rect::new_from_x_y(0, 0, 10, 10);
+        let wall = Rect::new_from_x_y(30, -20, 2, 50);
+
+        let hit = sweep(&from, Point { x: 40, y: 0 }, &wall).unwrap();
+
+        assert_eq!(hit.normal, Point { x: -1, y: 0 });
+        assert_eq!(hit.time, 0.5);
+        assert_eq!(sweep(&from, Point { x: 19, y: 0 }, &wall), None);
+    }
+
+    #[test]
+    fn tells_landing_on_top_from_running_into_the_side() {
+        let platform = Rect::new_from_x_y(100, 100, 200, 50);
+
+        let landing = Rect::new_from_x_y(80, 70, 30, 20);
+        let hit = sweep(&landing, Point { x: 5, y: 20 }, &platform).unwrap();
+        assert!(hit.on_top());
+
+        let running_into = Rect::new_from_x_y(65, 90, 30, 20);
+        let hit = sweep(&running_into, Point { x: 10, y: 5 }, &platform).unwrap();
+        assert_eq!(hit.normal, Point { x: -1, y: 0 });
+    }
+
+    #[test]
+    fn overlapping_boxes_touch_at_the_face_they_are_least_far_through() {
+        let platform = Rect::new_from_x_y(100, 100, 200, 50);
+        // Sunk a little into the platform, near its end
+        let standing = Rect::new_from_x_y(280, 75, 30, 30);
+
+        let hit = sweep(&standing, Point { x: 5, y: 20 }, &platform).unwrap();
+        assert_eq!(hit.time, 0.0);
+        assert!(hit.on_top());
+        assert_eq!(sweep(&standing, Point { x: 5, y: -20 }, &platform), None);
+
+        let walking_off = Rect::new_from_x_y(297, 75, 30, 30);
+        assert_eq!(sweep(&walking_off, Point { x: 5, y: 20 }, &platform), None);
+    }
+
+    #[test]
+    fn rests_on_a_raised_floor_until_it_is_lowered() {
+        let mut body = standing();
+        body.position.y = 100;
+        assert_eq!(body.step(), Some(ContactChange::Left(Contact::Ground)));
+        body.set_floor(500);
+
+        let changes: Vec<ContactChange> = (0..30).filter_map(|_| body.step()).collect();
+        assert_eq!(changes, vec![ContactChange::Landed(Contact::Platform)]);
+        assert_eq!(body.position.y, 500 - HEIGHT);
+
+        body.set_floor(GROUND);
+        let changes: Vec<ContactChange> = (0..30).filter_map(|_| body.step()).collect();
+        assert_eq!(
+            changes,
+            vec![
+                ContactChange::Left(Contact::Platform),
+                ContactChange::Landed(Contact::Ground)
+            ]
+        );
+    }
+}
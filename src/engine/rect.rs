@@ -1,4 +1,4 @@
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
 pub struct Point {
     pub x: i16,
     pub y: i16,
@@ -28,6 +28,11 @@ impl Rect {
         self.y() + self.height
     }
 
+    /// Halfway between `left` and `right`
+    pub fn center_x(&self) -> i16 {
+        self.x() + self.width / 2
+    }
+
     pub fn intersects(&self, other: &Rect) -> bool {
         self.left() < other.right()
             && self.right() >= other.left()
@@ -64,6 +69,13 @@ impl Rect {
 mod tests {
     use super::*;
 
+    #[test]
+    fn center_is_halfway_across() {
+        let rect = Rect::new_from_x_y(-20, 10, 90, 10);
+
+        assert_eq!(rect.center_x(), 25);
+    }
+
     #[test]
     fn two_rects_that_intersect_on_the_left() {
         let rect1 = Rect {
@@ -102,6 +102,19 @@ impl Renderer {
         self.context.stroke();
     }
 
+    /// Fill `rect` with `color`, always drawn unlike bounding boxes
+    pub fn fill_rect(&self, rect: &Rect, color: &str) {
+        self.context.save();
+        self.context.set_fill_style(&JsValue::from_str(color));
+        self.context.fill_rect(
+            rect.x().into(),
+            rect.y().into(),
+            rect.width.into(),
+            rect.height.into(),
+        );
+        self.context.restore();
+    }
+
     pub fn draw_text(&self, text: &str, location: &Point) -> Result<()> {
         self.context.set_font("16[t serif");
         self.context
@@ -110,8 +123,31 @@ impl Renderer {
 
         Ok(())
     }
+
+    /// Draw `text` at `location`. Use Options to set the font and color.
+    pub fn draw_text_ext(&self, text: &str, location: &Point, options: TextOptions) -> Result<()> {
+        self.context.save();
+        self.context.set_font(options.font);
+        self.context
+            .set_fill_style(&JsValue::from_str(options.color));
+        let result = self
+            .context
+            .fill_text(text, location.x.into(), location.y.into())
+            .map_err(|err| anyhow!("Error filling text {:#?}", err));
+        self.context.restore();
+
+        result
+    }
 }
 
 pub struct DrawImageOptions {
     pub flip_horizontally: bool,
 }
+
+#[derive(Clone, Copy)]
+pub struct TextOptions {
+    /// CSS color, e.g. "#FFFFFF"
+    pub color: &'static str,
+    /// CSS font, e.g. "20px serif"
+    pub font: &'static str,
+}
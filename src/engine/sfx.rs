@@ -0,0 +1,210 @@
+use super::audio::{Audio, PlaybackOptions, Sound};
+use anyhow::{anyhow, Result};
+use rand::{thread_rng, Rng};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+/// How a registered sound effect behaves when it is triggered
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SfxConfig {
+    /// Minimum seconds between two triggers. Triggers inside the window are dropped
+    pub cooldown: f64,
+    /// Maximum number of instances of the sound playing at once
+    pub max_voices: usize,
+    /// Base playback rate, where 1.0 is the sound's own pitch
+    pub pitch: f32,
+    /// Playback rate varies randomly by up to +/- this fraction
+    pub pitch_jitter: f32,
+    /// Base volume, where 1.0 is the sound's own level
+    pub volume: f32,
+    /// Volume varies randomly by up to +/- this fraction
+    pub volume_jitter: f32,
+}
+
+impl Default for SfxConfig {
+    fn default() -> Self {
+        SfxConfig {
+            cooldown: 0.0,
+            max_voices: 1,
+            pitch: 1.0,
+            pitch_jitter: 0.0,
+            volume: 1.0,
+            volume_jitter: 0.0,
+        }
+    }
+}
+
+/// Registry of sound effects keyed by name. Cloning shares the registry so
+/// voice and cooldown tracking is consistent across every clone (game object
+/// contexts get cloned on every state transition).
+#[derive(Clone)]
+pub struct Sfx {
+    /// None if there is no audio to play sounds through
+    audio: Option<Audio>,
+    sounds: Rc<RefCell<HashMap<String, SfxEntry>>>,
+}
+
+impl Sfx {
+    pub fn new(audio: Audio) -> Self {
+        Sfx {
+            audio: Some(audio),
+            sounds: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    /// A registry that never plays anything, for when the browser has no
+    /// audio
+    pub fn silent() -> Self {
+        Sfx {
+            audio: None,
+            sounds: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    /// Play the sound registered as `name` positioned in the stereo field
+    /// (see `audio::pan_for`), unless it is cooling down or all its voices
+    /// are busy. Returns whether the sound was started.
+    pub fn play_at(&self, name: &str, pan: f32) -> Result<bool> {
+        let Some(audio) = &self.audio else {
+            return Ok(false);
+        };
+        let mut sounds = self.sounds.borrow_mut();
+        let entry = sounds
+            .get_mut(name)
+            .ok_or_else(|| anyhow!("Sound effect '{name}' is not registered"))?;
+
+        let now = audio.current_time();
+        match entry
+            .voices
+            .trigger(now, entry.sound.duration(), &mut thread_rng())
+        {
+            Some(options) => audio
+                .play_sound(&entry.sound, PlaybackOptions { pan, ..options })
+                .map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Add (or replace) the sound effect played for `name`
+    pub fn register(&self, name: &str, sound: Sound, config: SfxConfig) {
+        self.sounds.borrow_mut().insert(
+            name.to_string(),
+            SfxEntry {
+                sound,
+                voices: Voices::new(config),
+            },
+        );
+    }
+}
+
+struct SfxEntry {
+    sound: Sound,
+    voices: Voices,
+}
+
+/// Tracks playing instances of one sound effect on the audio clock
+#[derive(Debug)]
+struct Voices {
+    config: SfxConfig,
+    /// Audio clock time at which each playing voice ends
+    ends: Vec<f64>,
+    last_trigger: Option<f64>,
+}
+
+impl Voices {
+    fn new(config: SfxConfig) -> Self {
+        Voices {
+            config,
+            ends: Vec::new(),
+            last_trigger: None,
+        }
+    }
+
+    /// Claim a voice at audio clock time `now` for a sound lasting `duration`
+    /// seconds at normal speed. Returns the randomized playback options to use,
+    /// or None when the trigger must be dropped.
+    fn trigger(&mut self, now: f64, duration: f64, rng: &mut impl Rng) -> Option<PlaybackOptions> {
+        if let Some(last) = self.last_trigger {
+            if now - last < self.config.cooldown {
+                return None;
+            }
+        }
+
+        self.ends.retain(|end| *end > now);
+        if self.ends.len() >= self.config.max_voices {
+            return None;
+        }
+
+        let playback_rate = self.config.pitch * (1.0 + jitter(rng, self.config.pitch_jitter));
+        let volume = (self.config.volume * (1.0 + jitter(rng, self.config.volume_jitter))).max(0.0);
+
+        self.last_trigger = Some(now);
+        self.ends.push(now + duration / f64::from(playback_rate));
+
+        Some(PlaybackOptions {
+            playback_rate,
+            volume,
+            ..PlaybackOptions::default()
+        })
+    }
+}
+
+fn jitter(rng: &mut impl Rng, amount: f32) -> f32 {
+    if amount > 0.0 {
+        rng.gen_range(-amount..=amount)
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn triggers_beyond_max_voices_are_dropped_until_a_voice_ends() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut voices = Voices::new(SfxConfig {
+            max_voices: 2,
+            ..SfxConfig::default()
+        });
+
+        assert!(voices.trigger(0.0, 1.0, &mut rng).is_some());
+        assert!(voices.trigger(0.1, 1.0, &mut rng).is_some());
+        assert!(voices.trigger(0.2, 1.0, &mut rng).is_none());
+        assert!(voices.trigger(1.05, 1.0, &mut rng).is_some());
+    }
+
+    #[test]
+    fn triggers_inside_the_cooldown_are_dropped() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut voices = Voices::new(SfxConfig {
+            cooldown: 0.5,
+            max_voices: 4,
+            ..SfxConfig::default()
+        });
+
+        assert!(voices.trigger(0.0, 0.1, &mut rng).is_some());
+        assert!(voices.trigger(0.4, 0.1, &mut rng).is_none());
+        assert!(voices.trigger(0.5, 0.1, &mut rng).is_some());
+    }
+
+    #[test]
+    fn pitch_and_volume_stay_within_jitter() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut voices = Voices::new(SfxConfig {
+            max_voices: usize::MAX,
+            pitch_jitter: 0.1,
+            volume: 0.5,
+            volume_jitter: 0.2,
+            ..SfxConfig::default()
+        });
+
+        for i in 0..100 {
+            let options = voices.trigger(f64::from(i), 0.0, &mut rng).unwrap();
+
+            assert!((0.9..=1.1).contains(&options.playback_rate));
+            assert!((0.4..=0.6).contains(&options.volume));
+        }
+    }
+}
@@ -16,6 +16,20 @@ impl Sheet {
         serde_wasm_bindgen::from_value::<Sheet>(json)
             .map_err(|err| anyhow!("Error deserializing {} {:#?}", json_path, err))
     }
+
+    /// Names in `frame_names` with no matching frame in this sheet, sorted and
+    /// without duplicates
+    pub fn missing_frames(&self, frame_names: &[String]) -> Vec<String> {
+        let mut missing: Vec<String> = frame_names
+            .iter()
+            .filter(|name| !self.frames.contains_key(*name))
+            .cloned()
+            .collect();
+        missing.sort();
+        missing.dedup();
+
+        missing
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -32,3 +46,119 @@ pub struct SheetRect {
     pub w: i16,
     pub h: i16,
 }
+
+/// The frames a game object may request from a loaded `Sheet`
+pub struct FrameRequirements<'a> {
+    pub frames: Vec<String>,
+    pub sheet: &'a Sheet,
+    pub sheet_name: &'a str,
+}
+
+/// Check every requirement against its sheet and fail with a single report
+/// listing all missing frames, so a bad sprite sheet is caught at load time
+/// instead of panicking mid-game the first time a state reaches the frame.
+pub fn validate_frames(requirements: &[FrameRequirements]) -> Result<()> {
+    let report: Vec<String> = requirements
+        .iter()
+        .filter_map(|requirement| {
+            let missing = requirement.sheet.missing_frames(&requirement.frames);
+            if missing.is_empty() {
+                None
+            } else {
+                Some(format!(
+                    "{} is missing {} frame(s): {}",
+                    requirement.sheet_name,
+                    missing.len(),
+                    missing.join(", ")
+                ))
+            }
+        })
+        .collect();
+
+    if report.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Sprite sheet validation failed\n{}",
+            report.join("\n")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sheet_with(names: &[&str]) -> Sheet {
+        let rect = SheetRect {
+            x: 0,
+            y: 0,
+            w: 1,
+            h: 1,
+        };
+        let frames = names
+            .iter()
+            .map(|name| {
+                (
+                    name.to_string(),
+                    Cell {
+                        frame: rect.clone(),
+                        sprite_source_size: rect.clone(),
+                    },
+                )
+            })
+            .collect();
+
+        Sheet { frames }
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn missing_frames_are_sorted_and_deduplicated() {
+        let sheet = sheet_with(&["a.png"]);
+
+        let missing = sheet.missing_frames(&names(&["c.png", "a.png", "b.png", "c.png"]));
+
+        assert_eq!(missing, names(&["b.png", "c.png"]));
+    }
+
+    #[test]
+    fn validation_passes_when_all_frames_are_present() {
+        let sheet = sheet_with(&["a.png", "b.png"]);
+
+        let result = validate_frames(&[FrameRequirements {
+            frames: names(&["a.png", "b.png"]),
+            sheet: &sheet,
+            sheet_name: "test.json",
+        }]);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn validation_reports_every_sheet_with_missing_frames() {
+        let first = sheet_with(&["a.png"]);
+        let second = sheet_with(&[]);
+
+        let err = validate_frames(&[
+            FrameRequirements {
+                frames: names(&["a.png", "b.png"]),
+                sheet: &first,
+                sheet_name: "first.json",
+            },
+            FrameRequirements {
+                frames: names(&["c.png"]),
+                sheet: &second,
+                sheet_name: "second.json",
+            },
+        ])
+        .unwrap_err()
+        .to_string();
+
+        assert!(err.contains("first.json is missing 1 frame(s): b.png"));
+        assert!(err.contains("second.json is missing 1 frame(s): c.png"));
+    }
+}
@@ -0,0 +1,59 @@
+use crate::browser;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// Key-value persistence, so game data can be saved in the browser or kept in
+/// memory (e.g. for tests)
+pub trait Storage {
+    fn get(&self, key: &str) -> Option<String>;
+    fn set(&mut self, key: &str, value: &str) -> Result<()>;
+}
+
+/// The browser's `localStorage`
+pub struct LocalStorage;
+
+impl Storage for LocalStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        browser::local_storage()
+            .ok()
+            .and_then(|storage| storage.get_item(key).ok().flatten())
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        browser::local_storage()?
+            .set_item(key, value)
+            .map_err(|err| anyhow!("Error saving '{key}' to local storage {:#?}", err))
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryStorage {
+    items: HashMap<String, String>,
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.items.get(key).cloned()
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        self.items.insert(key.to_string(), value.to_string());
+
+        Ok(())
+    }
+}
+
+/// `LocalStorage` when the browser allows it (it may not, e.g. in private
+/// browsing), otherwise `MemoryStorage` so data at least lasts the session
+pub fn browser_storage() -> Box<dyn Storage> {
+    match browser::local_storage() {
+        Ok(_) => Box::new(LocalStorage),
+        Err(err) => {
+            error!(
+                "Local storage unavailable, nothing will be saved {:#?}",
+                err
+            );
+            Box::<MemoryStorage>::default()
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::f32::consts::PI;
+
+/// Seconds faded in and out at either end of a tone to avoid clicks
+const FADE: f32 = 0.005;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Waveform {
+    Sawtooth,
+    Sine,
+    Square,
+    Triangle,
+}
+
+/// A simple synthesized sound: one oscillator sliding linearly from
+/// `frequency` to `end_frequency` over `duration` seconds
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tone {
+    pub duration: f32,
+    pub end_frequency: f32,
+    pub frequency: f32,
+    /// Peak amplitude, from 0.0 (silent) to 1.0
+    pub volume: f32,
+    pub waveform: Waveform,
+}
+
+impl Tone {
+    pub const SILENCE: Tone = Tone {
+        duration: 0.05,
+        end_frequency: 0.0,
+        frequency: 0.0,
+        volume: 0.0,
+        waveform: Waveform::Sine,
+    };
+}
+
+/// Mono samples for `tone` at `sample_rate` samples per second
+pub fn samples(tone: &Tone, sample_rate: f32) -> Vec<f32> {
+    let length = ((tone.duration * sample_rate).round() as usize).max(1);
+    let mut phase = 0.0_f32;
+
+    (0..length)
+        .map(|i| {
+            let t = i as f32 / sample_rate;
+            let progress = i as f32 / length as f32;
+            let frequency = tone.frequency + (tone.end_frequency - tone.frequency) * progress;
+            let sample = oscillate(tone.waveform, phase) * tone.volume * envelope(t, tone.duration);
+
+            phase = (phase + frequency / sample_rate).fract();
+
+            sample
+        })
+        .collect()
+}
+
+/// Mono samples for `tones` played one after the other, e.g. a short tune
+pub fn sequence_samples(tones: &[Tone], sample_rate: f32) -> Vec<f32> {
+    tones
+        .iter()
+        .flat_map(|tone| samples(tone, sample_rate))
+        .collect()
+}
+
+/// Value in -1.0..=1.0 of `waveform` at `phase` (0.0..1.0 through a cycle)
+fn oscillate(waveform: Waveform, phase: f32) -> f32 {
+    match waveform {
+        Waveform::Sawtooth => 2.0 * phase - 1.0,
+        Waveform::Sine => (2.0 * PI * phase).sin(),
+        Waveform::Square => {
+            if phase < 0.5 {
+                1.0
+            } else {
+                -1.0
+            }
+        }
+        Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+    }
+}
+
+fn envelope(t: f32, duration: f32) -> f32 {
+    (t / FADE).min((duration - t) / FADE).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TONE: Tone = Tone {
+        duration: 0.5,
+        end_frequency: 220.0,
+        frequency: 440.0,
+        volume: 0.5,
+        waveform: Waveform::Square,
+    };
+
+    #[test]
+    fn tone_has_one_sample_per_sample_period() {
+        assert_eq!(samples(&TONE, 8000.0).len(), 4000);
+    }
+
+    #[test]
+    fn tone_stays_within_volume_and_fades_at_both_ends() {
+        let samples = samples(&TONE, 8000.0);
+
+        assert!(samples.iter().all(|s| s.abs() <= TONE.volume));
+        assert_eq!(samples[0], 0.0);
+        assert!(samples.last().unwrap().abs() < 0.05);
+        assert_eq!(samples[2000].abs(), TONE.volume);
+    }
+
+    #[test]
+    fn a_sequence_plays_its_tones_in_order() {
+        let quiet = Tone {
+            volume: 0.1,
+            ..TONE
+        };
+        let samples = sequence_samples(&[TONE, quiet], 8000.0);
+
+        assert_eq!(samples.len(), 8000);
+        assert_eq!(samples[2000].abs(), TONE.volume);
+        assert_eq!(samples[6000].abs(), quiet.volume);
+    }
+
+    #[test]
+    fn silence_is_silent() {
+        assert!(samples(&Tone::SILENCE, 8000.0).iter().all(|s| *s == 0.0));
+    }
+}
@@ -1,28 +1,51 @@
+mod achievements;
+mod daily_run;
+mod difficulty;
 mod dog;
+mod editor;
+mod event_bus;
 mod event_queue;
+mod event_trace;
 pub mod game_states;
+mod high_scores;
+mod levels;
 mod obstacles;
 mod red_hat_boy;
+mod score;
+pub mod seed;
 mod segments;
+mod sound_effects;
+mod telemetry;
 mod walk;
 
 use crate::engine::{
-    audio::Audio, image::load_image, input::KeyState, rect::Rect, renderer::Renderer, sheet::Sheet,
-    sprite_sheet::SpriteSheet, Game,
+    image::load_image,
+    input::KeyState,
+    rect::Rect,
+    renderer::Renderer,
+    sheet::{validate_frames, FrameRequirements, Sheet},
+    sprite_sheet::SpriteSheet,
+    storage, Game,
 };
+use achievements::{AchievementDefinitions, Achievements, ACHIEVEMENTS_CONFIG};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use difficulty::{Difficulty, DIFFICULTY_CONFIG};
 use dog::Dog;
-use event_queue::EventPublisher;
+use event_queue::{EventPublisher, Events};
 use game_states::WalkTheDogStateMachine;
-use red_hat_boy::{context::Sfx, RedHatBoy};
-use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+use levels::{Levels, LEVELS_CONFIG};
+use red_hat_boy::RedHatBoy;
+use segments::definitions::{SegmentDefinitions, SEGMENTS_CONFIG};
+use sound_effects::AudioReactions;
+use std::{cell::RefCell, rc::Rc};
+use telemetry::{FetchTransport, Telemetry};
 use walk::Walk;
 
-const GRAVITY: i16 = 1;
 const TERMINAL_VELOCITY: i16 = 20;
 
 const HEIGHT: i16 = 600;
+const WIDTH: i16 = 600;
 
 const RUNNING_SPEED: i16 = 4;
 
@@ -39,7 +62,7 @@ impl WalkTheDog {
 #[async_trait(?Send)]
 impl Game for WalkTheDog {
     fn draw(&self, renderer: &Renderer) {
-        renderer.clear(&Rect::new_from_x_y(0, 0, 600, HEIGHT));
+        renderer.clear(&Rect::new_from_x_y(0, 0, WIDTH, HEIGHT));
 
         if let Some(machine) = &self.machine {
             machine.draw(renderer);
@@ -49,27 +72,41 @@ impl Game for WalkTheDog {
     async fn initialize(&self) -> Result<Box<dyn Game>> {
         match self.machine {
             None => {
-                let audio = Audio::new()?;
-                let sfx = Sfx::new(
-                    audio.load_sound("SFX_Jump_23.mp3").await?,
-                    audio.load_sound("vgdeathsound.ogg").await?,
-                    audio.load_sound("slide.wav").await?,
-                );
-                let background_music = audio.load_sound("background_song.mp3").await?;
-
-                audio.play_looping_sound(&background_music)?;
+                let sfx = sound_effects::load().await;
 
-                let events = Rc::new(RefCell::new(VecDeque::new()));
+                let events = Rc::new(RefCell::new(Events::default()));
                 let event_publisher = EventPublisher::new(events.clone());
 
+                let boy_sheet = Sheet::load("rhb.json").await?;
+                let dog_sheet = Sheet::load("dog.json").await?;
+                let tiles_sheet = Sheet::load("tiles.json").await?;
+                let segment_definitions = SegmentDefinitions::load(SEGMENTS_CONFIG).await;
+                let levels = Levels::load(LEVELS_CONFIG, &segment_definitions).await;
+                validate_frames(&[
+                    FrameRequirements {
+                        frames: RedHatBoy::required_frames(),
+                        sheet: &boy_sheet,
+                        sheet_name: "rhb.json",
+                    },
+                    FrameRequirements {
+                        frames: Dog::required_frames(),
+                        sheet: &dog_sheet,
+                        sheet_name: "dog.json",
+                    },
+                    FrameRequirements {
+                        frames: segment_definitions.required_sprites(),
+                        sheet: &tiles_sheet,
+                        sheet_name: "tiles.json",
+                    },
+                ])?;
+
                 let boy = RedHatBoy::new(
-                    audio,
-                    sfx,
-                    Sheet::load("rhb.json").await?,
+                    boy_sheet,
                     load_image("rhb.png").await?,
+                    event_publisher.clone(),
                 );
                 let dog = Dog::new(
-                    Sheet::load("dog.json").await?,
+                    dog_sheet,
                     load_image("dog.png").await?,
                     event_publisher.clone(),
                 );
@@ -77,19 +114,31 @@ impl Game for WalkTheDog {
                 let background = load_image("BG.png").await?;
                 let stone = load_image("Stone.png").await?;
 
-                let segment_tiles = SpriteSheet::new(
-                    Sheet::load("tiles.json").await?,
-                    load_image("tiles.png").await?,
+                let segment_tiles = SpriteSheet::new(tiles_sheet, load_image("tiles.png").await?);
+
+                let achievements = Achievements::load(
+                    AchievementDefinitions::load(ACHIEVEMENTS_CONFIG).await,
+                    storage::browser_storage(),
                 );
 
                 let machine = WalkTheDogStateMachine::new(Walk::new(
+                    achievements,
+                    AudioReactions::new(sfx, sound_effects::panning_from_url()),
                     background,
                     boy,
+                    Difficulty::load(DIFFICULTY_CONFIG).await,
                     dog,
                     event_publisher,
                     events,
+                    levels,
                     stone,
+                    segment_definitions,
                     segment_tiles,
+                    Telemetry::load(
+                        telemetry::endpoint_from_url(),
+                        storage::browser_storage(),
+                        Box::new(FetchTransport),
+                    ),
                 ));
 
                 Ok(Box::new(WalkTheDog {
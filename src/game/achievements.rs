@@ -0,0 +1,447 @@
+use super::{
+    event_bus::Subscription,
+    event_queue::{EventSubscriber, GameEvent, GameEventKind},
+    score::RunStats,
+    WIDTH,
+};
+use crate::{
+    browser,
+    engine::{
+        rect::{Point, Rect},
+        renderer::{Renderer, TextOptions},
+        storage::Storage,
+    },
+};
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+pub const ACHIEVEMENTS_CONFIG: &str = "achievements.json";
+const STORAGE_KEY: &str = "walk_the_dog.achievements";
+/// How long each unlocked achievement is announced for
+const TOAST_FRAMES: u32 = 180;
+
+const TOAST_COLOR: &str = "rgba(0, 0, 0, 0.7)";
+const TOAST_BOX: Rect = Rect::new_from_x_y(300, 10, WIDTH - 310, 62);
+const TOAST_LINE_HEIGHT: i16 = 18;
+const TOAST_TITLE: TextOptions = TextOptions {
+    color: "#FFD700",
+    font: "14px 'Ken Future'",
+};
+const TOAST_TEXT: TextOptions = TextOptions {
+    color: "#FFFFFF",
+    font: "14px 'Ken Future'",
+};
+
+/// The achievements that can be unlocked, as loaded from `ACHIEVEMENTS_CONFIG`
+#[derive(Clone, Debug, Deserialize)]
+pub struct AchievementDefinitions {
+    pub achievements: Vec<Achievement>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Achievement {
+    pub description: String,
+    pub goal: Goal,
+    /// Saved once unlocked, so it must not change
+    pub id: String,
+    pub name: String,
+}
+
+/// What unlocks an achievement, within a single run
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum Goal {
+    /// `count` events of kind `event`. Any event in `reset_by` starts the
+    /// count over.
+    Events {
+        count: u32,
+        event: GameEventKind,
+        #[serde(default)]
+        reset_by: Vec<GameEventKind>,
+    },
+    /// A run statistic reaching `at_least`
+    Stat { at_least: i32, stat: Stat },
+}
+
+/// The `RunStats` a `Goal::Stat` can be about
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Stat {
+    Bonus,
+    BonusEvents,
+    Meters,
+    Score,
+    Seconds,
+}
+
+impl Stat {
+    fn of(&self, stats: &RunStats) -> i32 {
+        match self {
+            Stat::Bonus => stats.bonus,
+            Stat::BonusEvents => stats.bonus_events as i32,
+            Stat::Meters => stats.meters,
+            Stat::Score => stats.score,
+            Stat::Seconds => stats.seconds as i32,
+        }
+    }
+}
+
+impl AchievementDefinitions {
+    /// Load and check the achievements at `json_path`, falling back to the
+    /// achievements built into the game if they can't be used
+    pub async fn load(json_path: &str) -> Self {
+        match AchievementDefinitions::fetch(json_path).await {
+            Ok(definitions) => definitions,
+            Err(err) => {
+                error!("Using the built-in achievements {:#?}", err);
+                AchievementDefinitions::built_in()
+            }
+        }
+    }
+
+    /// The achievements shipped with the game
+    pub fn built_in() -> Self {
+        serde_json::from_str(include_str!("../../static/achievements.json"))
+            .expect("Built-in achievements should deserialize")
+    }
+
+    /// Check ids are unique and every goal can be reached
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+        let mut ids = HashSet::new();
+        for achievement in &self.achievements {
+            if !ids.insert(&achievement.id) {
+                problems.push(format!("{} is defined more than once", achievement.id));
+            }
+            if let Goal::Events { count: 0, .. } = achievement.goal {
+                problems.push(format!("{} needs a count of at least 1", achievement.id));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Achievement validation failed\n{}",
+                problems.join("\n")
+            ))
+        }
+    }
+
+    async fn fetch(json_path: &str) -> Result<Self> {
+        let json = browser::fetch_json(json_path).await?;
+        let definitions = serde_wasm_bindgen::from_value::<AchievementDefinitions>(json)
+            .map_err(|err| anyhow!("Error deserializing {} {:#?}", json_path, err))?;
+        definitions.validate()?;
+
+        Ok(definitions)
+    }
+}
+
+/// An unlocked achievement being announced
+#[derive(Debug, PartialEq)]
+struct Toast {
+    description: String,
+    frames_left: u32,
+    name: String,
+}
+
+/// Tracks progress towards each achievement from the events and stats of the
+/// current run, saving unlocked achievements to `Storage` and announcing them
+pub struct Achievements {
+    definitions: Vec<Achievement>,
+    /// Events counted towards each `Goal::Events` this run, by achievement id
+    progress: HashMap<String, u32>,
+    storage: Box<dyn Storage>,
+    toasts: VecDeque<Toast>,
+    unlocked: BTreeSet<String>,
+}
+
+impl Achievements {
+    /// Load which achievements were already unlocked. Missing or unreadable
+    /// records start out with none.
+    pub fn load(definitions: AchievementDefinitions, storage: Box<dyn Storage>) -> Self {
+        let unlocked = storage
+            .get(STORAGE_KEY)
+            .and_then(|json| match serde_json::from_str(&json) {
+                Ok(unlocked) => Some(unlocked),
+                Err(err) => {
+                    error!("Ignoring unreadable achievements {:#?}", err);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Achievements {
+            definitions: definitions.achievements,
+            progress: HashMap::new(),
+            storage,
+            toasts: VecDeque::new(),
+            unlocked,
+        }
+    }
+
+    /// The latest unlocked achievement, while it is being announced
+    pub fn draw(&self, renderer: &Renderer) {
+        let Some(toast) = self.toasts.front() else {
+            return;
+        };
+
+        renderer.fill_rect(&TOAST_BOX, TOAST_COLOR);
+        let lines = [
+            ("Achievement unlocked", TOAST_TITLE),
+            (toast.name.as_str(), TOAST_TEXT),
+            (toast.description.as_str(), TOAST_TEXT),
+        ];
+        for (row, (line, options)) in lines.into_iter().enumerate() {
+            let position = Point {
+                x: TOAST_BOX.x() + 10,
+                y: TOAST_BOX.y() + 16 + row as i16 * TOAST_LINE_HEIGHT,
+            };
+            if let Err(err) = renderer.draw_text_ext(line, &position, options) {
+                error!("Could not draw the achievement {:#?}", err);
+            }
+        }
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.contains(id)
+    }
+
+    /// How many achievements have been unlocked, for the run summary
+    pub fn summary_html(&self) -> String {
+        let unlocked = self
+            .definitions
+            .iter()
+            .filter(|achievement| self.is_unlocked(&achievement.id))
+            .count();
+
+        format!(
+            "<p class='achievements'>Achievements {unlocked} of {}</p>",
+            self.definitions.len()
+        )
+    }
+
+    /// Check the stat goals against this frame's `stats`, and count down the
+    /// announcement being shown
+    pub fn track(&mut self, stats: &RunStats) {
+        let reached: Vec<String> = self
+            .locked()
+            .filter(|achievement| match achievement.goal {
+                Goal::Stat { at_least, stat } => stat.of(stats) >= at_least,
+                Goal::Events { .. } => false,
+            })
+            .map(|achievement| achievement.id.clone())
+            .collect();
+        reached.iter().for_each(|id| self.unlock(id));
+
+        if let Some(toast) = self.toasts.front_mut() {
+            toast.frames_left = toast.frames_left.saturating_sub(1);
+            if toast.frames_left == 0 {
+                self.toasts.pop_front();
+            }
+        }
+    }
+
+    fn locked(&self) -> impl Iterator<Item = &Achievement> {
+        self.definitions
+            .iter()
+            .filter(|achievement| !self.unlocked.contains(&achievement.id))
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let json = serde_json::to_string(&self.unlocked)?;
+
+        self.storage.set(STORAGE_KEY, &json)
+    }
+
+    fn unlock(&mut self, id: &str) {
+        let Some(achievement) = self.definitions.iter().find(|found| found.id == id) else {
+            return;
+        };
+        if !self.unlocked.insert(id.to_string()) {
+            return;
+        }
+
+        log!("Achievement unlocked: {}", achievement.name);
+        self.toasts.push_back(Toast {
+            description: achievement.description.clone(),
+            frames_left: TOAST_FRAMES,
+            name: achievement.name.clone(),
+        });
+
+        if let Err(err) = self.save() {
+            error!("Could not save achievements {:#?}", err);
+        }
+    }
+}
+
+impl EventSubscriber for Achievements {
+    fn name(&self) -> String {
+        "Achievements".to_string()
+    }
+
+    fn process_event(&mut self, event: &GameEvent) {
+        let kind = event.kind();
+        if kind == GameEventKind::GameStarted {
+            self.progress.clear();
+        }
+
+        let mut reached = Vec::new();
+        for achievement in &self.definitions {
+            if self.unlocked.contains(&achievement.id) {
+                continue;
+            }
+
+            let Goal::Events {
+                count,
+                event,
+                reset_by,
+            } = &achievement.goal
+            else {
+                continue;
+            };
+
+            let progress = self.progress.get(&achievement.id).copied().unwrap_or(0);
+            let progress = if reset_by.contains(&kind) {
+                0
+            } else if *event == kind {
+                progress + 1
+            } else {
+                progress
+            };
+            if progress >= *count {
+                reached.push(achievement.id.clone());
+            }
+            self.progress.insert(achievement.id.clone(), progress);
+        }
+
+        reached.iter().for_each(|id| self.unlock(id));
+    }
+
+    fn subscriptions(&self) -> Vec<Subscription> {
+        let mut kinds = vec![GameEventKind::GameStarted];
+        for achievement in &self.definitions {
+            if let Goal::Events {
+                event, reset_by, ..
+            } = &achievement.goal
+            {
+                kinds.extend(std::iter::once(event).chain(reset_by));
+            }
+        }
+        let mut subscribed = HashSet::new();
+        kinds.retain(|kind| subscribed.insert(*kind));
+
+        kinds.into_iter().map(Subscription::to).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::storage::MemoryStorage;
+
+    fn achievements(json: &str) -> Achievements {
+        Achievements::load(
+            serde_json::from_str(json).unwrap(),
+            Box::<MemoryStorage>::default(),
+        )
+    }
+
+    fn cleared(id: &str) -> GameEvent {
+        GameEvent::BoyClearedBarrier { id: id.to_string() }
+    }
+
+    const STREAK: &str = r#"{"achievements": [{
+        "id": "skipper",
+        "name": "Skipper",
+        "description": "Clear 3 stones without sliding",
+        "goal": {"type": "events", "event": "BoyClearedBarrier", "count": 3, "reset_by": ["BoySlid"]}
+    }]}"#;
+
+    #[test]
+    fn built_in_achievements_are_valid() {
+        let definitions = AchievementDefinitions::built_in();
+
+        assert!(definitions.validate().is_ok());
+        assert!(!definitions.achievements.is_empty());
+    }
+
+    #[test]
+    fn duplicate_ids_and_zero_counts_fail_validation() {
+        let mut definitions: AchievementDefinitions = serde_json::from_str(STREAK).unwrap();
+        let mut unreachable = definitions.achievements[0].clone();
+        unreachable.goal = Goal::Events {
+            count: 0,
+            event: GameEventKind::DogTooFar,
+            reset_by: vec![],
+        };
+        definitions.achievements.push(unreachable);
+
+        let message = definitions.validate().unwrap_err().to_string();
+
+        assert!(message.contains("skipper is defined more than once"));
+        assert!(message.contains("skipper needs a count of at least 1"));
+    }
+
+    #[test]
+    fn event_goals_start_over_on_reset_events_and_new_runs() {
+        let mut achievements = achievements(STREAK);
+        achievements.process_event(&cleared("b1"));
+        achievements.process_event(&cleared("b2"));
+        achievements.process_event(&GameEvent::BoySlid);
+        achievements.process_event(&cleared("b3"));
+        achievements.process_event(&cleared("b4"));
+        achievements.process_event(&GameEvent::GameStarted);
+        achievements.process_event(&cleared("b5"));
+        achievements.process_event(&cleared("b6"));
+        assert!(!achievements.is_unlocked("skipper"));
+
+        achievements.process_event(&cleared("b7"));
+        assert!(achievements.is_unlocked("skipper"));
+        assert_eq!(
+            achievements.subscriptions(),
+            vec![
+                Subscription::to(GameEventKind::GameStarted),
+                Subscription::to(GameEventKind::BoyClearedBarrier),
+                Subscription::to(GameEventKind::BoySlid)
+            ]
+        );
+    }
+
+    #[test]
+    fn stat_goals_unlock_once_and_are_saved_and_announced() {
+        let json = r#"{"achievements": [{
+            "id": "marathon",
+            "name": "Marathon",
+            "description": "Walk 100m",
+            "goal": {"type": "stat", "stat": "meters", "at_least": 100}
+        }]}"#;
+        let mut achievements = achievements(json);
+        let mut stats = RunStats {
+            bonus: 0,
+            bonus_events: 0,
+            meters: 99,
+            score: 99,
+            seconds: 20,
+        };
+        achievements.track(&stats);
+        assert!(achievements.toasts.is_empty());
+
+        stats.meters = 100;
+        achievements.track(&stats);
+        achievements.track(&stats);
+        assert_eq!(achievements.toasts.len(), 1);
+        assert_eq!(achievements.toasts[0].frames_left, TOAST_FRAMES - 2);
+        (0..TOAST_FRAMES).for_each(|_| achievements.track(&stats));
+        assert!(achievements.toasts.is_empty());
+
+        let mut storage = MemoryStorage::default();
+        storage
+            .set(STORAGE_KEY, &achievements.storage.get(STORAGE_KEY).unwrap())
+            .unwrap();
+        let reloaded = Achievements::load(serde_json::from_str(json).unwrap(), Box::new(storage));
+        assert!(reloaded.is_unlocked("marathon"));
+    }
+}
@@ -0,0 +1,244 @@
+use crate::engine::{
+    rect::Point,
+    renderer::{Renderer, TextOptions},
+    storage::Storage,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Days of results kept, enough to show recent daily bests
+const MAX_DAYS: usize = 31;
+const STORAGE_KEY: &str = "walk_the_dog.daily_run";
+
+const PANEL_POSITION: Point = Point { x: 170, y: 200 };
+const PANEL_LINE_HEIGHT: i16 = 30;
+const PANEL_TEXT: TextOptions = TextOptions {
+    color: "#FFFFFF",
+    font: "20px 'Ken Future'",
+};
+
+/// Seed of the daily run on `date` (YYYY-MM-DD, UTC), the same for everyone
+/// playing that day: the date's digits, e.g. 20261019
+pub fn daily_seed(date: &str) -> u64 {
+    date.chars()
+        .filter_map(|c| c.to_digit(10))
+        .fold(0, |seed, digit| seed * 10 + u64::from(digit))
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+struct DayResult {
+    attempts: u32,
+    best: i32,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct DailyHistory {
+    /// Results by date (YYYY-MM-DD), only the most recent `MAX_DAYS`
+    days: BTreeMap<String, DayResult>,
+    last_played: Option<String>,
+    /// Consecutive days played up to `last_played`
+    streak: u32,
+}
+
+/// Daily Run results, saved to `Storage` after every attempt
+pub struct DailyRun {
+    history: DailyHistory,
+    storage: Box<dyn Storage>,
+}
+
+impl DailyRun {
+    /// Load the saved results. Missing or unreadable results start out empty.
+    pub fn load(storage: Box<dyn Storage>) -> Self {
+        let history = storage
+            .get(STORAGE_KEY)
+            .and_then(|json| match serde_json::from_str(&json) {
+                Ok(history) => Some(history),
+                Err(err) => {
+                    error!("Ignoring unreadable daily run results {:#?}", err);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        DailyRun { history, storage }
+    }
+
+    pub fn attempts(&self, date: &str) -> u32 {
+        self.history
+            .days
+            .get(date)
+            .map_or(0, |result| result.attempts)
+    }
+
+    pub fn best(&self, date: &str) -> Option<i32> {
+        self.history.days.get(date).map(|result| result.best)
+    }
+
+    pub fn draw(&self, renderer: &Renderer, date: &str) {
+        let best = self
+            .best(date)
+            .map_or("-".to_string(), |best| best.to_string());
+        let lines = [
+            format!("Daily Run {date}"),
+            format!("Best {best}"),
+            format!("Attempts {}", self.attempts(date)),
+            format!("Streak {} days", self.streak(date)),
+        ];
+
+        for (row, line) in lines.iter().enumerate() {
+            let position = Point {
+                x: PANEL_POSITION.x,
+                y: PANEL_POSITION.y + row as i16 * PANEL_LINE_HEIGHT,
+            };
+            if let Err(err) = renderer.draw_text_ext(line, &position, PANEL_TEXT) {
+                error!("Could not draw the daily run {:#?}", err);
+            }
+        }
+    }
+
+    /// Count an attempt at the daily run on `date` that scored `score`.
+    /// Returns true if it is the best score of the day so far.
+    pub fn record(&mut self, date: &str, score: i32) -> bool {
+        let streak = self.streak(date);
+        if self.history.last_played.as_deref() != Some(date) {
+            self.history.streak = streak + 1;
+            self.history.last_played = Some(date.to_string());
+        }
+
+        let result = self.history.days.entry(date.to_string()).or_default();
+        let new_best = result.attempts == 0 || score > result.best;
+        result.attempts += 1;
+        if new_best {
+            result.best = score;
+        }
+        while self.history.days.len() > MAX_DAYS {
+            self.history.days.pop_first();
+        }
+
+        if let Err(err) = self.save() {
+            error!("Could not save daily run results {:#?}", err);
+        }
+
+        new_best
+    }
+
+    /// Consecutive days played up to `date`. A streak lasts until the end of the
+    /// day after it was last played.
+    pub fn streak(&self, date: &str) -> u32 {
+        let last_played = self.history.last_played.as_deref().and_then(day_number);
+
+        match (last_played, day_number(date)) {
+            (Some(last), Some(today)) if today - last <= 1 => self.history.streak,
+            _ => 0,
+        }
+    }
+
+    pub fn summary_html(&self, date: &str, new_best: bool) -> String {
+        let callout = if new_best {
+            "<p class='new-best'>New daily best!</p>"
+        } else {
+            ""
+        };
+
+        format!(
+            "{callout}<dl class='daily-run'>\
+            <dt>Daily Run</dt><dd>{date}</dd>\
+            <dt>Best today</dt><dd>{}</dd>\
+            <dt>Attempts</dt><dd>{}</dd>\
+            <dt>Streak</dt><dd>{} days</dd>\
+            </dl>",
+            self.best(date).unwrap_or_default(),
+            self.attempts(date),
+            self.streak(date)
+        )
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let json = serde_json::to_string(&self.history)?;
+
+        self.storage.set(STORAGE_KEY, &json)
+    }
+}
+
+/// Days since 1970-01-01 of a YYYY-MM-DD date
+fn day_number(date: &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+
+    // Howard Hinnant's days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::storage::MemoryStorage;
+
+    fn daily_run() -> DailyRun {
+        DailyRun::load(Box::<MemoryStorage>::default())
+    }
+
+    #[test]
+    fn everyone_gets_the_same_seed_on_the_same_day() {
+        assert_eq!(daily_seed("2026-10-19"), 20261019);
+        assert_ne!(daily_seed("2026-10-19"), daily_seed("2026-10-20"));
+    }
+
+    #[test]
+    fn keeps_a_separate_best_and_attempt_count_per_day() {
+        let mut daily_run = daily_run();
+
+        assert!(daily_run.record("2026-10-19", 30));
+        assert!(!daily_run.record("2026-10-19", 20));
+        assert!(daily_run.record("2026-10-19", 40));
+        assert!(daily_run.record("2026-10-20", 10));
+
+        assert_eq!(daily_run.best("2026-10-19"), Some(40));
+        assert_eq!(daily_run.attempts("2026-10-19"), 3);
+        assert_eq!(daily_run.best("2026-10-20"), Some(10));
+        assert_eq!(daily_run.attempts("2026-10-21"), 0);
+    }
+
+    #[test]
+    fn streak_counts_consecutive_days_across_month_ends() {
+        let mut daily_run = daily_run();
+        for date in ["2026-10-30", "2026-10-31", "2026-10-31", "2026-11-01"] {
+            daily_run.record(date, 1);
+        }
+
+        assert_eq!(daily_run.streak("2026-11-01"), 3);
+        assert_eq!(daily_run.streak("2026-11-02"), 3);
+        assert_eq!(daily_run.streak("2026-11-03"), 0);
+
+        daily_run.record("2026-11-03", 1);
+        assert_eq!(daily_run.streak("2026-11-03"), 1);
+    }
+
+    #[test]
+    fn results_survive_reloading_from_storage() {
+        let mut storage = MemoryStorage::default();
+        let mut daily_run = daily_run();
+        daily_run.record("2026-10-19", 25);
+        let json = serde_json::to_string(&daily_run.history).unwrap();
+        storage.set(STORAGE_KEY, &json).unwrap();
+
+        let daily_run = DailyRun::load(Box::new(storage));
+
+        assert_eq!(daily_run.best("2026-10-19"), Some(25));
+        assert_eq!(daily_run.streak("2026-10-19"), 1);
+    }
+
+    #[test]
+    fn day_numbers_count_from_the_unix_epoch() {
+        assert_eq!(day_number("1970-01-01"), Some(0));
+        assert_eq!(day_number("2000-03-01"), Some(11_017));
+        assert_eq!(day_number("not a date"), None);
+    }
+}
@@ -0,0 +1,177 @@
+use crate::browser;
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub const DIFFICULTY_CONFIG: &str = "difficulty.json";
+/// Most pixels per frame the speed bonus adds, however steep the curve
+pub const MAX_SPEED_BONUS: i16 = 12;
+
+/// A value that moves linearly from `start` (difficulty level 0.0) to `end`
+/// (difficulty level 1.0)
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub struct Ramp {
+    pub end: f32,
+    pub start: f32,
+}
+
+impl Ramp {
+    pub fn at(&self, level: f32) -> f32 {
+        self.start + (self.end - self.start) * level
+    }
+}
+
+/// How a run gets harder the further the boy walks
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Difficulty {
+    /// Pixels between the end of one segment and the start of the next.
+    /// Never below 0, so segments don't overlap.
+    pub obstacle_buffer: Ramp,
+    /// Meters walked before the difficulty peaks
+    pub ramp_meters: i32,
+    /// Pixels per frame added to the walking speed, up to `MAX_SPEED_BONUS`
+    pub speed_bonus: Ramp,
+    /// Relative chance of each segment being picked next. Segments missing
+    /// from the table keep a weight of 1.0.
+    #[serde(default)]
+    pub segment_weights: HashMap<String, Ramp>,
+}
+
+impl Difficulty {
+    /// Load the difficulty curve from `json_path`, falling back to the
+    /// built-in curve if it can't be read
+    pub async fn load(json_path: &str) -> Self {
+        match Difficulty::fetch(json_path).await {
+            Ok(difficulty) => difficulty,
+            Err(err) => {
+                error!("Using the default difficulty curve {:#?}", err);
+                Difficulty::default()
+            }
+        }
+    }
+
+    /// Difficulty from 0.0 at the start of a run up to 1.0 after `ramp_meters`
+    pub fn level(&self, meters: i32) -> f32 {
+        if self.ramp_meters <= 0 {
+            return 1.0;
+        }
+
+        (meters as f32 / self.ramp_meters as f32).clamp(0.0, 1.0)
+    }
+
+    pub fn obstacle_buffer(&self, level: f32) -> i16 {
+        (self.obstacle_buffer.at(level).round() as i16).max(0)
+    }
+
+    pub fn segment_weight(&self, segment: &str, level: f32) -> f32 {
+        self.segment_weights
+            .get(segment)
+            .map_or(1.0, |weight| weight.at(level).max(0.0))
+    }
+
+    pub fn speed_bonus(&self, level: f32) -> i16 {
+        (self.speed_bonus.at(level).round() as i16).clamp(0, MAX_SPEED_BONUS)
+    }
+
+    async fn fetch(json_path: &str) -> Result<Self> {
+        let json = browser::fetch_json(json_path).await?;
+
+        serde_wasm_bindgen::from_value::<Difficulty>(json)
+            .map_err(|err| anyhow!("Error deserializing {} {:#?}", json_path, err))
+    }
+}
+
+impl Default for Difficulty {
+    /// A flat curve: the game as it played before difficulty ramped up
+    fn default() -> Self {
+        let flat = |value| Ramp {
+            end: value,
+            start: value,
+        };
+        Difficulty {
+            obstacle_buffer: flat(20.0),
+            ramp_meters: 1,
+            speed_bonus: flat(0.0),
+            segment_weights: HashMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Difficulty {
+        serde_json::from_str(include_str!("../../static/difficulty.json")).unwrap()
+    }
+
+    #[test]
+    fn level_ramps_up_over_ramp_meters_then_holds() {
+        let difficulty = config();
+
+        assert_eq!(difficulty.level(0), 0.0);
+        assert_eq!(difficulty.level(difficulty.ramp_meters / 2), 0.5);
+        assert_eq!(difficulty.level(difficulty.ramp_meters), 1.0);
+        assert_eq!(difficulty.level(difficulty.ramp_meters * 3), 1.0);
+    }
+
+    #[test]
+    fn shipped_curve_speeds_up_closes_gaps_and_favours_harder_segments() {
+        let difficulty = config();
+
+        assert!(difficulty.speed_bonus(1.0) > difficulty.speed_bonus(0.0));
+        assert!(difficulty.obstacle_buffer(1.0) < difficulty.obstacle_buffer(0.0));
+        let share = |segment, level| {
+            let total: f32 = difficulty
+                .segment_weights
+                .keys()
+                .map(|s| difficulty.segment_weight(s, level))
+                .sum();
+            difficulty.segment_weight(segment, level) / total
+        };
+        assert!(share("stone_on_platform", 1.0) > share("stone_on_platform", 0.0));
+        assert!(share("platform_low", 1.0) < share("platform_low", 0.0));
+    }
+
+    #[test]
+    fn speed_bonus_is_capped() {
+        let mut difficulty = config();
+        difficulty.speed_bonus = Ramp {
+            end: 100.0,
+            start: -5.0,
+        };
+
+        assert_eq!(difficulty.speed_bonus(0.0), 0);
+        assert_eq!(difficulty.speed_bonus(1.0), MAX_SPEED_BONUS);
+    }
+
+    #[test]
+    fn segments_never_overlap() {
+        let mut difficulty = config();
+        difficulty.obstacle_buffer = Ramp {
+            end: -40.0,
+            start: 20.0,
+        };
+
+        assert_eq!(difficulty.obstacle_buffer(0.5), 0);
+        assert_eq!(difficulty.obstacle_buffer(1.0), 0);
+    }
+
+    #[test]
+    fn shipped_curve_starts_where_the_default_does() {
+        let difficulty = config();
+        let default = Difficulty::default();
+
+        assert_eq!(difficulty.speed_bonus(0.0), default.speed_bonus(0.0));
+        assert_eq!(
+            difficulty.obstacle_buffer(0.0),
+            default.obstacle_buffer(0.0)
+        );
+        for segment in difficulty.segment_weights.keys() {
+            assert_eq!(
+                difficulty.segment_weight(segment, 0.0),
+                default.segment_weight(segment, 0.0)
+            );
+        }
+    }
+}
@@ -1,15 +1,18 @@
-mod context;
+pub mod context;
 mod state_machine;
-mod states;
+pub mod states;
 
-use super::event_queue::{self, EventSubscriber, GameEvent};
+use super::{
+    event_bus::Subscription,
+    event_queue::{self, EventSubscriber, GameEvent, GameEventKind},
+};
 use crate::engine::{
     rect::Rect,
     renderer::{DrawImageOptions, Renderer},
     sheet::{Cell, Sheet},
 };
 use state_machine::{DogStateMachine, Event};
-use states::DogState;
+use states::{sprite_frame_name, DogState, ANIMATIONS};
 use web_sys::HtmlImageElement;
 
 pub struct Dog {
@@ -27,10 +30,20 @@ impl Dog {
         Dog {
             image,
             sprite_sheet,
-            state_machine: DogStateMachine::Running(DogState::new(event_publisher)),
+            state_machine: DogStateMachine::Running(DogState::new(event_publisher.named("Dog"))),
         }
     }
 
+    /// Every sprite sheet frame name the state machine can request
+    pub fn required_frames() -> Vec<String> {
+        ANIMATIONS
+            .iter()
+            .flat_map(|(prefix, frames)| {
+                (0..=*frames).map(move |frame| sprite_frame_name(prefix, frame))
+            })
+            .collect()
+    }
+
     pub fn info(&self) -> String {
         let ctx = self.state_machine.context();
         let bb = self.bounding_box();
@@ -40,7 +53,7 @@ impl Dog {
             bb.top(),
             bb.right(),
             bb.bottom(),
-            ctx.velocity,
+            ctx.body.velocity,
             self.state_machine.state_name(),
             ctx.info()
         )
@@ -55,29 +68,17 @@ impl Dog {
     }
 
     pub fn moving_up(&self) -> bool {
-        self.state_machine.context().velocity.y <= 0
+        self.state_machine.context().body.velocity.y <= 0
     }
 
-    pub fn reset(dog: Self) -> Self {
-        Dog::new(
-            dog.sprite_sheet,
-            dog.image,
-            dog.state_machine.context().event_publisher.clone(),
-        )
+    /// Back to running from the start, in place like `RedHatBoy::reset`
+    pub fn reset(&mut self) {
+        let event_publisher = self.state_machine.context().event_publisher.clone();
+        self.state_machine = DogStateMachine::Running(DogState::new(event_publisher));
     }
 
     pub fn bounding_box(&self) -> Rect {
-        const Y_OFFSET: i16 = 8;
-        const WIDTH_OFFSET: i16 = 50;
-        let x_offset: i16 = if self.moving_left() { 20 } else { 35 };
-
-        let mut bounding_box = self.destination_box();
-        bounding_box.set_x(bounding_box.x() + x_offset);
-        bounding_box.width -= WIDTH_OFFSET;
-        bounding_box.position.y += Y_OFFSET;
-        bounding_box.height -= Y_OFFSET;
-
-        bounding_box
+        collision_box(self.destination_box(), self.moving_left())
     }
 
     pub fn draw(&self, renderer: &Renderer) {
@@ -117,8 +118,8 @@ impl Dog {
         let sprite = self.current_sprite();
 
         Rect::new_from_x_y(
-            self.state_machine.context().position.x + sprite.sprite_source_size.x,
-            self.state_machine.context().position.y + sprite.sprite_source_size.y,
+            self.state_machine.context().body.position.x + sprite.sprite_source_size.x,
+            self.state_machine.context().body.position.y + sprite.sprite_source_size.y,
             sprite.frame.w,
             sprite.frame.h,
         )
@@ -137,19 +138,75 @@ impl EventSubscriber for Dog {
     }
 
     fn process_event(&mut self, event: &GameEvent) {
-        match event {
-            GameEvent::BoyHitsObstacle => self.transition(Event::Worry, event),
-            GameEvent::DogExitsPlatform { .. } => self.transition(Event::OffPlatform, event),
-            GameEvent::DogHitMark { .. } => self.transition(Event::Jump, event),
-            GameEvent::DogLandedOnGround => self.transition(Event::LandOnGround, event),
-            GameEvent::DogLandedOnPlatform { platform_top, .. } => {
-                self.transition(Event::LandOn(*platform_top), event)
-            }
-            GameEvent::DogTooClose | GameEvent::DogTooFar => {
-                self.transition(Event::TurnAround, event)
-            }
-            GameEvent::GameStarted => self.transition(Event::Flee, event),
-            _ => (),
-        };
+        if let Some(command) = command_for(event) {
+            self.transition(command, event);
+        }
+    }
+
+    /// e.g. "Running left"
+    fn state(&self) -> Option<String> {
+        let direction = if self.moving_left() { "left" } else { "right" };
+
+        Some(format!("{} {direction}", self.state_machine.state_name()))
+    }
+
+    fn subscriptions(&self) -> Vec<Subscription> {
+        REACTS_TO.into_iter().map(Subscription::to).collect()
+    }
+}
+
+/// Kinds of `GameEvent` that `command_for` turns into a command
+const REACTS_TO: [GameEventKind; 8] = [
+    GameEventKind::BoyHitsObstacle,
+    GameEventKind::DogExitsPlatform,
+    GameEventKind::DogHitMark,
+    GameEventKind::DogLandedOnGround,
+    GameEventKind::DogLandedOnPlatform,
+    GameEventKind::DogTooClose,
+    GameEventKind::DogTooFar,
+    GameEventKind::GameStarted,
+];
+
+/// What `event` tells the dog to do, if anything
+fn command_for(event: &GameEvent) -> Option<Event> {
+    match event {
+        GameEvent::BoyHitsObstacle { .. } => Some(Event::Worry),
+        GameEvent::DogExitsPlatform => Some(Event::OffPlatform),
+        GameEvent::DogHitMark { .. } => Some(Event::Jump),
+        GameEvent::DogLandedOnGround => Some(Event::LandOnGround),
+        GameEvent::DogLandedOnPlatform { platform_top, .. } => Some(Event::LandOn(*platform_top)),
+        GameEvent::DogTooClose | GameEvent::DogTooFar => Some(Event::TurnAround),
+        GameEvent::GameStarted => Some(Event::Flee),
+        _ => None,
+    }
+}
+
+/// The part of a sprite drawn at `destination_box` that obstacles and marks
+/// collide with. The dog's head leads, so the box shifts with its direction.
+pub fn collision_box(destination_box: Rect, moving_left: bool) -> Rect {
+    const Y_OFFSET: i16 = 8;
+    const WIDTH_OFFSET: i16 = 50;
+    let x_offset: i16 = if moving_left { 20 } else { 35 };
+
+    let mut bounding_box = destination_box;
+    bounding_box.set_x(bounding_box.x() + x_offset);
+    bounding_box.width -= WIDTH_OFFSET;
+    bounding_box.position.y += Y_OFFSET;
+    bounding_box.height -= Y_OFFSET;
+
+    bounding_box
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::event_queue::handled_kinds;
+
+    #[test]
+    fn subscribes_to_exactly_the_events_it_reacts_to() {
+        assert_eq!(
+            handled_kinds(|event| command_for(event).is_some()),
+            REACTS_TO.into_iter().collect()
+        );
     }
 }
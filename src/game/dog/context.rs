@@ -1,6 +1,13 @@
 use crate::{
-    engine::rect::Point,
-    game::{self, event_queue::GameEvent, RUNNING_SPEED},
+    engine::{
+        physics::{Contact, ContactChange, KinematicBody},
+        rect::Point,
+    },
+    game::{
+        self,
+        event_queue::{GameEvent, Phase},
+        RUNNING_SPEED,
+    },
 };
 
 pub const DOG_GROUND: i16 = game::HEIGHT - DOG_HEIGHT;
@@ -12,40 +19,37 @@ pub const JUMP_SPEED: i16 = -25;
 
 #[derive(Clone)]
 pub struct DogContext {
+    pub body: KinematicBody,
     distance_max: i16,
     pub distance_min: i16,
     pub event_publisher: game::event_queue::EventPublisher,
-    pub floor: i16,
     pub frame: u8,
-    pub position: Point,
     pub scrolling_velocity: i16,
-    pub velocity: Point,
 }
 
 impl DogContext {
     pub fn new(event_publisher: game::event_queue::EventPublisher) -> Self {
-        let floor = DOG_GROUND;
-        let position = Point { x: 10, y: floor };
-        let velocity = Point {
-            x: RUNNING_SPEED,
-            y: 0,
+        let position = Point {
+            x: 10,
+            y: DOG_GROUND,
         };
+        let mut body =
+            KinematicBody::new(position, DOG_HEIGHT, game::HEIGHT, game::TERMINAL_VELOCITY);
+        body.velocity.x = RUNNING_SPEED;
 
         DogContext {
+            body,
             distance_max: 1000,
             distance_min: 300,
             event_publisher,
-            floor,
             frame: 0,
-            position,
             scrolling_velocity: 0,
-            velocity,
         }
     }
 
     pub fn flee(mut self) -> Self {
         self.scrolling_velocity = -RUNNING_SPEED;
-        self.velocity.x = if self.position.x > 650 {
+        self.body.velocity.x = if self.body.position.x > 650 {
             -2 * RUNNING_SPEED
         } else {
             0
@@ -58,16 +62,18 @@ impl DogContext {
     pub fn info(&self) -> String {
         format!(
             "pos={:?} v={:?} floor={:?}",
-            self.position, self.velocity, self.floor
+            self.body.position,
+            self.body.velocity,
+            self.body.floor()
         )
     }
 
     pub fn moving_left(&self) -> bool {
-        self.velocity.x < self.scrolling_velocity
+        self.body.velocity.x < self.scrolling_velocity
     }
 
     pub fn moving_right(&self) -> bool {
-        self.velocity.x >= self.scrolling_velocity
+        self.body.velocity.x >= self.scrolling_velocity
     }
 
     pub fn reset_frame(mut self) -> Self {
@@ -77,10 +83,10 @@ impl DogContext {
     }
 
     pub fn set_floor(mut self, bottom: i16) -> Self {
-        self.floor = bottom - DOG_HEIGHT;
+        self.body.set_floor(bottom);
         log!(
             "DogContext: set floor to {}",
-            if self.floor == DOG_GROUND {
+            if self.body.floor() == DOG_GROUND {
                 "Ground"
             } else {
                 "Platform"
@@ -92,9 +98,9 @@ impl DogContext {
 
     pub fn turn_around(mut self) -> Self {
         if self.scrolling_velocity < 0 {
-            self.velocity.x = RUNNING_SPEED + self.scrolling_velocity;
+            self.body.velocity.x = RUNNING_SPEED + self.scrolling_velocity;
         } else {
-            self.velocity.x *= -1;
+            self.body.velocity.x *= -1;
         }
         log!("Dog: turned around {}", self.info());
 
@@ -108,28 +114,21 @@ impl DogContext {
             self.frame = 0
         }
 
-        // vertical movement
-        let was_on_floor = self.on_ground();
-        if self.velocity.y < game::TERMINAL_VELOCITY {
-            self.velocity.y += game::GRAVITY;
-        }
-
-        self.position.y += self.velocity.y;
-
-        if self.position.y > self.floor {
-            self.position.y = self.floor;
-        }
-
-        if self.on_ground() && !was_on_floor {
-            self.event_publisher.publish(GameEvent::DogLandedOnGround);
+        // Handled at the end of this update, once the obstacles have seen
+        // where the dog is
+        if self.body.step() == Some(ContactChange::Landed(Contact::Ground)) {
+            self.event_publisher
+                .publish_in(Phase::EndOfUpdate, GameEvent::DogLandedOnGround);
         }
 
         // horizontal movement
-        self.position.x += self.velocity.x;
+        self.body.position.x += self.body.velocity.x;
         if self.too_close() {
-            self.event_publisher.publish(GameEvent::DogTooClose);
+            self.event_publisher
+                .publish_in(Phase::EndOfUpdate, GameEvent::DogTooClose);
         } else if self.too_far() {
-            self.event_publisher.publish(GameEvent::DogTooFar);
+            self.event_publisher
+                .publish_in(Phase::EndOfUpdate, GameEvent::DogTooFar);
         }
 
         self
@@ -137,7 +136,7 @@ impl DogContext {
 
     pub fn worry(mut self) -> Self {
         self.scrolling_velocity = 0;
-        self.velocity.x = RUNNING_SPEED;
+        self.body.velocity.x = RUNNING_SPEED;
         self.distance_min = 50;
         log!("Dog worries {}", self.info());
 
@@ -145,18 +144,14 @@ impl DogContext {
     }
 
     fn on_floor(&self) -> bool {
-        self.position.y == self.floor
-    }
-
-    fn on_ground(&self) -> bool {
-        self.floor == DOG_GROUND && self.on_floor()
+        self.body.contact() != Contact::Airborne
     }
 
     fn too_close(&self) -> bool {
-        self.moving_left() && self.on_floor() && self.position.x < self.distance_min
+        self.moving_left() && self.on_floor() && self.body.position.x < self.distance_min
     }
 
     fn too_far(&self) -> bool {
-        self.moving_right() && self.on_floor() && self.position.x > self.distance_max
+        self.moving_right() && self.on_floor() && self.body.position.x > self.distance_max
     }
 }
@@ -1,7 +1,19 @@
 pub mod jumping;
 pub mod running;
 
-use super::context::DogContext;
+use super::context::{DogContext, JUMPING_FRAMES, RUNNING_FRAMES};
+
+/// (sprite prefix, last frame count) for every animation the state machine
+/// can display
+pub const ANIMATIONS: [(&str, u8); 2] = [
+    (jumping::JUMPING_FRAME_PREFIX, JUMPING_FRAMES),
+    (running::RUNNING_FRAME_PREFIX, RUNNING_FRAMES),
+];
+
+pub fn sprite_frame_name(prefix: &str, frame: u8) -> String {
+    let animation_frame = frame / 3;
+    format!("{prefix}_{animation_frame:03}.png")
+}
 
 #[derive(Clone)]
 pub struct DogState<S> {
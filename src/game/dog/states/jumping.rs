@@ -1,13 +1,14 @@
-use super::{running::Running, DogState};
+use super::{running::Running, sprite_frame_name, DogState};
 use crate::game::{dog::context::JUMPING_FRAMES, HEIGHT};
 
+pub const JUMPING_FRAME_PREFIX: &str = "l";
+
 #[derive(Clone)]
 pub struct Jumping;
 
 impl DogState<Jumping> {
     pub fn frame_name(&self) -> String {
-        let animation_frame = self.context().frame / 3;
-        format!("l_{animation_frame:03}.png")
+        sprite_frame_name(JUMPING_FRAME_PREFIX, self.context().frame)
     }
 
     pub fn jump_off_platform(self) -> DogState<Jumping> {
@@ -1,10 +1,12 @@
-use super::{jumping::Jumping, DogState};
+use super::{jumping::Jumping, sprite_frame_name, DogState};
 use crate::game::{
     self,
     dog::context::{DogContext, JUMP_SPEED, RUNNING_FRAMES},
     HEIGHT,
 };
 
+pub const RUNNING_FRAME_PREFIX: &str = "rr";
+
 #[derive(Clone)]
 pub struct Running;
 
@@ -28,13 +30,12 @@ impl DogState<Running> {
     }
 
     pub fn frame_name(&self) -> String {
-        let animation_frame = self.context().frame / 3;
-        format!("rr_{animation_frame:03}.png")
+        sprite_frame_name(RUNNING_FRAME_PREFIX, self.context().frame)
     }
 
     pub fn jump(mut self) -> DogState<Jumping> {
         log!("Dog Running->Jumping");
-        self.context.velocity.y = JUMP_SPEED;
+        self.context.body.velocity.y = JUMP_SPEED;
 
         DogState {
             context: self.context.reset_frame(),
@@ -0,0 +1,395 @@
+use super::{
+    obstacles::{barrier, obstacle_mark::mark_rect, platform},
+    segments::{
+        definitions::{MarkSide, ObstacleSpec, SegmentDefinitions},
+        registry::{Elevation, SegmentSpec},
+    },
+    HEIGHT, WIDTH,
+};
+use crate::{
+    browser,
+    engine::rect::{Point, Rect},
+};
+use anyhow::{anyhow, Result};
+
+/// Open the editor straight away with `?editor` in the page URL
+pub const EDITOR_QUERY_PARAM: &str = "editor";
+/// Pixels the cursor moves per step. Obstacles are placed on this grid.
+pub const GRID: i16 = 10;
+
+const STARTING_CURSOR: Point = Point { x: 200, y: 420 };
+
+/// What gets placed at the cursor
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Tool {
+    /// A floating platform using the named sprite set
+    Platform(String),
+    Stone,
+}
+
+/// A segment being authored in the editor. Positions are relative to the
+/// start of the segment, like in `segments.json`.
+#[derive(Clone, Debug)]
+pub struct Editor {
+    camera_x: i16,
+    cursor: Point,
+    definitions: SegmentDefinitions,
+    obstacles: Vec<ObstacleSpec>,
+    /// Width and height of the stone image
+    stone: (i16, i16),
+    tool: Tool,
+}
+
+impl Editor {
+    pub fn new(definitions: SegmentDefinitions, stone: (i16, i16)) -> Self {
+        Editor {
+            camera_x: 0,
+            cursor: STARTING_CURSOR,
+            definitions,
+            obstacles: vec![],
+            stone,
+            tool: Tool::Stone,
+        }
+    }
+
+    /// Area covered by `obstacle`
+    pub fn bounds(&self, obstacle: &ObstacleSpec) -> Rect {
+        match obstacle {
+            ObstacleSpec::Platform {
+                sprite_set, x, y, ..
+            } => {
+                let boxes = self
+                    .definitions
+                    .sprite_sets
+                    .get(sprite_set)
+                    .map(|sprite_set| sprite_set.bounding_boxes.as_slice())
+                    .unwrap_or_default();
+                let left = boxes.iter().map(|b| b.x).min().unwrap_or(0);
+                let top = boxes.iter().map(|b| b.y).min().unwrap_or(0);
+                let right = boxes.iter().map(|b| b.x + b.width).max().unwrap_or(0);
+                let bottom = boxes.iter().map(|b| b.y + b.height).max().unwrap_or(0);
+
+                Rect::new_from_x_y(x + left, y + top, right - left, bottom - top)
+            }
+            ObstacleSpec::Stone { x, y, .. } => {
+                Rect::new_from_x_y(*x, *y, self.stone.0, self.stone.1)
+            }
+        }
+    }
+
+    /// Left edge of the view
+    pub fn camera_x(&self) -> i16 {
+        self.camera_x
+    }
+
+    pub fn cursor(&self) -> Point {
+        self.cursor
+    }
+
+    /// Step through no marks, left, right and both sides for the obstacle
+    /// under the cursor. False if there is no obstacle there.
+    pub fn cycle_marks(&mut self) -> bool {
+        let Some(index) = self.obstacle_at_cursor() else {
+            return false;
+        };
+        let marks = match &mut self.obstacles[index] {
+            ObstacleSpec::Platform { marks, .. } | ObstacleSpec::Stone { marks, .. } => marks,
+        };
+        *marks = match marks.as_slice() {
+            [] => vec![MarkSide::Left],
+            [MarkSide::Left] => vec![MarkSide::Right],
+            [MarkSide::Right] => vec![MarkSide::Left, MarkSide::Right],
+            _ => vec![],
+        };
+
+        true
+    }
+
+    /// The segment as JSON for the `segments` list of `segments.json`
+    pub fn export(&self, name: &str) -> Result<String> {
+        serde_json::to_string_pretty(&self.segment(name))
+            .map_err(|err| anyhow!("Error exporting segment {name} {:#?}", err))
+    }
+
+    /// Where the dog's jump marks will be generated, the same way
+    /// `Barrier` and `Platform` place them
+    pub fn mark_previews(&self) -> Vec<(MarkSide, Rect)> {
+        self.obstacles
+            .iter()
+            .flat_map(|obstacle| {
+                let bounds = self.bounds(obstacle);
+                let (marks, left, right) = match obstacle {
+                    ObstacleSpec::Platform { marks, x, y, .. } => {
+                        let position = Point { x: *x, y: *y };
+                        (
+                            marks,
+                            platform::left_mark_position(position),
+                            platform::right_mark_position(position, bounds.right()),
+                        )
+                    }
+                    ObstacleSpec::Stone { marks, .. } => (
+                        marks,
+                        barrier::left_mark_position(&bounds),
+                        barrier::right_mark_position(&bounds),
+                    ),
+                };
+
+                marks.iter().map(move |side| match side {
+                    MarkSide::Left => (*side, mark_rect(left)),
+                    MarkSide::Right => (*side, mark_rect(right)),
+                })
+            })
+            .collect()
+    }
+
+    /// Move the cursor by whole grid steps, scrolling to keep it in view
+    pub fn move_cursor(&mut self, columns: i16, rows: i16) {
+        self.cursor.x = (self.cursor.x + columns * GRID).max(0);
+        self.cursor.y = (self.cursor.y + rows * GRID).clamp(0, HEIGHT);
+
+        if self.cursor.x < self.camera_x {
+            self.camera_x = self.cursor.x;
+        }
+        if self.cursor.x > self.camera_x + WIDTH - GRID {
+            self.camera_x = self.cursor.x - WIDTH + GRID;
+        }
+    }
+
+    /// Switch to the next tool: the stone, then each platform sprite set
+    pub fn next_tool(&mut self) {
+        let mut tools = vec![Tool::Stone];
+        let mut sprite_sets: Vec<&String> = self.definitions.sprite_sets.keys().collect();
+        sprite_sets.sort();
+        tools.extend(
+            sprite_sets
+                .into_iter()
+                .map(|name| Tool::Platform(name.clone())),
+        );
+
+        let current = tools
+            .iter()
+            .position(|tool| *tool == self.tool)
+            .unwrap_or(0);
+        self.tool = tools[(current + 1) % tools.len()].clone();
+    }
+
+    pub fn obstacles(&self) -> &[ObstacleSpec] {
+        &self.obstacles
+    }
+
+    /// Scroll the view by `x` pixels, taking the cursor along to the nearest
+    /// grid point
+    pub fn pan(&mut self, x: i16) {
+        let camera_x = (self.camera_x + x).max(0);
+        let cursor_x = self.cursor.x + camera_x - self.camera_x;
+        self.cursor.x = (cursor_x + GRID / 2) / GRID * GRID;
+        self.camera_x = camera_x;
+    }
+
+    /// Place the current tool's obstacle with its top left corner at the
+    /// cursor, resting on the ground if the cursor is below it
+    pub fn place(&mut self) {
+        let mut obstacle = match &self.tool {
+            Tool::Platform(sprite_set) => ObstacleSpec::Platform {
+                marks: vec![],
+                sprite_set: sprite_set.clone(),
+                x: self.cursor.x,
+                y: self.cursor.y,
+            },
+            Tool::Stone => ObstacleSpec::Stone {
+                marks: vec![],
+                x: self.cursor.x,
+                y: self.cursor.y,
+            },
+        };
+        let overhang = self.bounds(&obstacle).bottom() - HEIGHT;
+        if overhang > 0 {
+            match &mut obstacle {
+                ObstacleSpec::Platform { y, .. } | ObstacleSpec::Stone { y, .. } => *y -= overhang,
+            }
+        }
+
+        self.obstacles.push(obstacle);
+    }
+
+    /// Remove the obstacle under the cursor, the most recently placed first.
+    /// False if there is no obstacle there.
+    pub fn remove(&mut self) -> bool {
+        match self.obstacle_at_cursor() {
+            Some(index) => {
+                self.obstacles.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The segment as it would be loaded from `segments.json`, with
+    /// conservative rules to adjust by hand: entered and left on the ground,
+    /// no cooldown, available from the start.
+    pub fn segment(&self, name: &str) -> SegmentSpec {
+        let mut obstacles = self.obstacles.clone();
+        obstacles.sort_by_key(|obstacle| self.bounds(obstacle).x());
+
+        SegmentSpec {
+            cooldown: 0,
+            enters_from: vec![Elevation::Ground],
+            exit: Elevation::Ground,
+            min_difficulty: 0.0,
+            name: name.to_string(),
+            obstacles,
+            weight: 1.0,
+        }
+    }
+
+    pub fn tool(&self) -> &Tool {
+        &self.tool
+    }
+
+    fn obstacle_at_cursor(&self) -> Option<usize> {
+        let cursor = self.cursor;
+
+        self.obstacles.iter().rposition(|obstacle| {
+            let bounds = self.bounds(obstacle);
+            (bounds.left()..=bounds.right()).contains(&cursor.x)
+                && (bounds.top()..=bounds.bottom()).contains(&cursor.y)
+        })
+    }
+}
+
+/// True if the page URL asks for the editor (e.g. `?editor`)
+pub fn requested_by_url() -> bool {
+    match browser::query_param(EDITOR_QUERY_PARAM) {
+        Ok(param) => param.is_some(),
+        Err(err) => {
+            error!("Could not read the editor flag from the URL {:#?}", err);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STONE: (i16, i16) = (90, 54);
+
+    fn editor() -> Editor {
+        Editor::new(SegmentDefinitions::built_in(), STONE)
+    }
+
+    #[test]
+    fn cursor_moves_on_the_grid_and_the_camera_follows() {
+        let mut editor = editor();
+
+        editor.move_cursor(50, 30);
+
+        assert_eq!(editor.cursor(), Point { x: 700, y: HEIGHT });
+        assert_eq!(editor.camera_x(), 700 - WIDTH + GRID);
+
+        editor.move_cursor(-100, 0);
+
+        assert_eq!(editor.cursor().x, 0);
+        assert_eq!(editor.camera_x(), 0);
+    }
+
+    #[test]
+    fn panning_keeps_the_cursor_on_the_grid() {
+        let mut editor = editor();
+
+        editor.pan(8);
+        editor.pan(8);
+
+        assert_eq!(editor.camera_x(), 16);
+        assert_eq!(editor.cursor().x, 220);
+
+        editor.pan(-100);
+
+        assert_eq!(editor.camera_x(), 0);
+        assert_eq!(editor.cursor().x, 200);
+    }
+
+    #[test]
+    fn stones_placed_below_the_ground_rest_on_it() {
+        let mut editor = editor();
+        editor.move_cursor(0, 30);
+
+        editor.place();
+
+        assert_eq!(
+            editor.obstacles(),
+            &[ObstacleSpec::Stone {
+                marks: vec![],
+                x: 200,
+                y: HEIGHT - STONE.1
+            }]
+        );
+    }
+
+    #[test]
+    fn marks_cycle_and_preview_where_the_obstacles_put_them() {
+        let mut editor = editor();
+        editor.move_cursor(0, 30);
+        editor.place();
+        let bounds = editor.bounds(&editor.obstacles()[0]);
+
+        assert!(editor.cycle_marks());
+        assert!(editor.cycle_marks());
+        assert!(editor.cycle_marks());
+
+        assert_eq!(
+            editor.mark_previews(),
+            vec![
+                (
+                    MarkSide::Left,
+                    mark_rect(barrier::left_mark_position(&bounds))
+                ),
+                (
+                    MarkSide::Right,
+                    mark_rect(barrier::right_mark_position(&bounds))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn removes_the_obstacle_under_the_cursor() {
+        let mut editor = editor();
+        editor.next_tool();
+        editor.place();
+
+        editor.move_cursor(-30, 0);
+        assert!(!editor.remove());
+
+        editor.move_cursor(40, 5);
+        assert!(editor.remove());
+        assert!(editor.obstacles().is_empty());
+    }
+
+    #[test]
+    fn exported_segments_load_as_segment_definitions() {
+        let mut editor = editor();
+        editor.next_tool();
+        editor.place();
+        editor.next_tool();
+        editor.move_cursor(-5, 30);
+        editor.place();
+        editor.cycle_marks();
+
+        let json = editor.export("custom").unwrap();
+        let mut definitions = SegmentDefinitions::built_in();
+        definitions
+            .segments
+            .push(serde_json::from_str(&json).unwrap());
+
+        assert!(definitions.validate().is_ok());
+        assert_eq!(
+            definitions.segments.last().unwrap().obstacles,
+            editor.segment("custom").obstacles
+        );
+        assert!(matches!(
+            definitions.segments.last().unwrap().obstacles[0],
+            ObstacleSpec::Stone { x: 150, .. }
+        ));
+    }
+}
@@ -0,0 +1,323 @@
+use super::{
+    event_queue::{EventSubscriber, GameEvent, GameEventKind},
+    event_trace::Delivery,
+};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::{Rc, Weak},
+};
+
+/// Priorities for `Subscription::with_priority`. Lower numbers hear an event
+/// first; subscribers with the same priority hear it in the order they
+/// subscribed.
+pub const FIRST: i32 = -100;
+pub const DEFAULT_PRIORITY: i32 = 0;
+pub const LAST: i32 = 100;
+
+/// An event kind a subscriber wants to hear about, optionally only when the
+/// event targets one id, e.g. a single platform or mark
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subscription {
+    pub kind: GameEventKind,
+    pub priority: i32,
+    pub target: Option<String>,
+}
+
+impl Subscription {
+    pub fn to(kind: GameEventKind) -> Self {
+        Subscription {
+            kind,
+            priority: DEFAULT_PRIORITY,
+            target: None,
+        }
+    }
+
+    /// Only events whose `GameEvent::target` is `id`
+    pub fn targeting(mut self, id: &str) -> Self {
+        self.target = Some(id.to_string());
+        self
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+type Topic = (GameEventKind, Option<String>);
+
+/// Subscribers are held weakly so they unsubscribe themselves by being dropped
+type WeakSubscriber = Weak<RefCell<dyn EventSubscriber>>;
+
+struct Route {
+    priority: i32,
+    sequence: u64,
+    subscriber: WeakSubscriber,
+}
+
+impl Route {
+    fn is_alive(&self) -> bool {
+        self.subscriber.strong_count() > 0
+    }
+}
+
+/// Routes each `GameEvent` to the subscribers registered for its kind and
+/// target, so dispatch cost depends on how many are interested rather than
+/// on how many exist. Only weak references are kept: once a subscriber's
+/// last `Rc` is dropped it hears nothing more and its routes are pruned.
+#[derive(Default)]
+pub struct EventBus {
+    next_sequence: u64,
+    routes: HashMap<Topic, Vec<Route>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        EventBus::default()
+    }
+
+    /// Register `subscriber` for everything in its `subscriptions`, for as
+    /// long as it is alive
+    pub fn subscribe<T: EventSubscriber + 'static>(&mut self, subscriber: &Rc<RefCell<T>>) {
+        self.prune();
+
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        let weak: WeakSubscriber = Rc::downgrade(subscriber) as WeakSubscriber;
+
+        for subscription in subscriber.borrow().subscriptions() {
+            let routes = self
+                .routes
+                .entry((subscription.kind, subscription.target))
+                .or_default();
+            let index = routes.partition_point(|route| {
+                (route.priority, route.sequence) <= (subscription.priority, sequence)
+            });
+            routes.insert(
+                index,
+                Route {
+                    priority: subscription.priority,
+                    sequence,
+                    subscriber: weak.clone(),
+                },
+            );
+        }
+    }
+
+    /// Number of live subscribers with at least one subscription
+    pub fn subscriber_count(&self) -> usize {
+        self.routes
+            .values()
+            .flatten()
+            .filter(|route| route.is_alive())
+            .map(|route| route.sequence)
+            .collect::<HashSet<u64>>()
+            .len()
+    }
+
+    /// Everyone alive and subscribed to `event`, in priority order. A
+    /// subscriber that matches both by kind and by target is only included
+    /// once.
+    pub fn recipients(&self, event: &GameEvent) -> Vec<Rc<RefCell<dyn EventSubscriber>>> {
+        let kind = event.kind();
+        let mut routes: Vec<&Route> = self
+            .routes
+            .get(&(kind, None))
+            .into_iter()
+            .chain(
+                event
+                    .target()
+                    .and_then(|target| self.routes.get(&(kind, Some(target.to_string())))),
+            )
+            .flatten()
+            .collect();
+        routes.sort_by_key(|route| (route.priority, route.sequence));
+        routes.dedup_by_key(|route| route.sequence);
+
+        routes
+            .into_iter()
+            .filter_map(|route| route.subscriber.upgrade())
+            .collect()
+    }
+
+    /// Send `event` to its recipients. Returns who got it, and how it
+    /// changed their state, in debug builds only since that is just for
+    /// the event trace.
+    pub fn dispatch(&self, event: &GameEvent) -> Vec<Delivery> {
+        let recipients = self.recipients(event);
+        if !cfg!(debug_assertions) {
+            for subscriber in recipients {
+                subscriber.borrow_mut().process_event(event);
+            }
+            return vec![];
+        }
+
+        recipients
+            .into_iter()
+            .map(|subscriber| {
+                let mut subscriber = subscriber.borrow_mut();
+                let before = subscriber.state();
+                subscriber.process_event(event);
+                let after = subscriber.state();
+
+                Delivery {
+                    subscriber: subscriber.name(),
+                    transition: before.zip(after).filter(|(before, after)| before != after),
+                }
+            })
+            .collect()
+    }
+
+    /// Forget the routes of subscribers that have been dropped
+    fn prune(&mut self) {
+        self.routes.retain(|_, routes| {
+            routes.retain(Route::is_alive);
+            !routes.is_empty()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Listener {
+        heard: Rc<RefCell<Vec<String>>>,
+        name: &'static str,
+        subscriptions: Vec<Subscription>,
+    }
+
+    impl EventSubscriber for Listener {
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        fn process_event(&mut self, _event: &GameEvent) {
+            self.heard.borrow_mut().push(self.name.to_string());
+        }
+
+        fn subscriptions(&self) -> Vec<Subscription> {
+            self.subscriptions.clone()
+        }
+    }
+
+    fn listener(
+        heard: &Rc<RefCell<Vec<String>>>,
+        name: &'static str,
+        subscriptions: Vec<Subscription>,
+    ) -> Rc<RefCell<Listener>> {
+        Rc::new(RefCell::new(Listener {
+            heard: Rc::clone(heard),
+            name,
+            subscriptions,
+        }))
+    }
+
+    fn hit_mark(id: &str) -> GameEvent {
+        GameEvent::DogHitMark { id: id.to_string() }
+    }
+
+    #[test]
+    fn only_subscribers_for_the_kind_and_target_hear_an_event() {
+        let heard = Rc::new(RefCell::new(Vec::new()));
+        let mut bus = EventBus::new();
+        let m1 = listener(
+            &heard,
+            "m1",
+            vec![Subscription::to(GameEventKind::DogHitMark).targeting("m1")],
+        );
+        let m2 = listener(
+            &heard,
+            "m2",
+            vec![Subscription::to(GameEventKind::DogHitMark).targeting("m2")],
+        );
+        let score = listener(
+            &heard,
+            "Score",
+            vec![Subscription::to(GameEventKind::DogHitMark)],
+        );
+        let dog = listener(
+            &heard,
+            "Dog",
+            vec![Subscription::to(GameEventKind::GameStarted)],
+        );
+        for subscriber in [&m1, &m2, &score, &dog] {
+            bus.subscribe(subscriber);
+        }
+
+        bus.dispatch(&hit_mark("m2"));
+
+        assert_eq!(*heard.borrow(), vec!["m2", "Score"]);
+    }
+
+    #[test]
+    fn lower_priorities_hear_first_and_ties_keep_subscription_order() {
+        let heard = Rc::new(RefCell::new(Vec::new()));
+        let mut bus = EventBus::new();
+        let started = Subscription::to(GameEventKind::GameStarted);
+        let subscribers = [
+            listener(&heard, "audio", vec![started.clone().with_priority(LAST)]),
+            listener(&heard, "dog", vec![started.clone()]),
+            listener(&heard, "score", vec![started.clone()]),
+            listener(&heard, "walk", vec![started.with_priority(FIRST)]),
+        ];
+        for subscriber in &subscribers {
+            bus.subscribe(subscriber);
+        }
+
+        bus.dispatch(&GameEvent::GameStarted);
+
+        assert_eq!(*heard.borrow(), vec!["walk", "dog", "score", "audio"]);
+    }
+
+    #[test]
+    fn a_subscriber_matching_by_kind_and_target_hears_an_event_once() {
+        let heard = Rc::new(RefCell::new(Vec::new()));
+        let mut bus = EventBus::new();
+        let m1 = listener(
+            &heard,
+            "m1",
+            vec![
+                Subscription::to(GameEventKind::DogHitMark),
+                Subscription::to(GameEventKind::DogHitMark).targeting("m1"),
+            ],
+        );
+        bus.subscribe(&m1);
+
+        bus.dispatch(&hit_mark("m1"));
+
+        assert_eq!(*heard.borrow(), vec!["m1"]);
+    }
+
+    #[test]
+    fn dropped_subscribers_hear_nothing_and_are_pruned() {
+        let heard = Rc::new(RefCell::new(Vec::new()));
+        let mut bus = EventBus::new();
+        let platform = listener(
+            &heard,
+            "p1",
+            vec![
+                Subscription::to(GameEventKind::DogExitsPlatform),
+                Subscription::to(GameEventKind::DogLandedOnPlatform).targeting("p1"),
+            ],
+        );
+        let barrier = listener(&heard, "barrier", vec![]);
+        bus.subscribe(&platform);
+        bus.subscribe(&barrier);
+        assert_eq!(bus.subscriber_count(), 1);
+
+        drop(platform);
+        bus.dispatch(&GameEvent::DogExitsPlatform);
+        assert!(heard.borrow().is_empty());
+        assert_eq!(bus.subscriber_count(), 0);
+
+        let dog = listener(
+            &heard,
+            "Dog",
+            vec![Subscription::to(GameEventKind::GameStarted)],
+        );
+        bus.subscribe(&dog);
+        assert_eq!(bus.routes.len(), 1);
+    }
+}
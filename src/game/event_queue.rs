@@ -1,39 +1,486 @@
-use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+use super::event_bus::Subscription;
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use std::collections::HashSet;
+use std::{cell::RefCell, collections::VecDeque, mem, rc::Rc};
+
+/// Most events published while others are being dispatched in one frame.
+/// Past it the rest of the cascade is dropped, so events that keep
+/// triggering each other can't stall the game.
+pub const CASCADE_LIMIT: usize = 32;
+
+/// When a published `GameEvent` is dispatched
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum Phase {
+    /// Straight after the event being dispatched when published by a
+    /// subscriber, otherwise the next time `Walk` dispatches any events
+    Immediate,
+    /// At the end of `Walk::update`, once the dog and obstacles have moved
+    EndOfUpdate,
+    /// At the start of the next `Walk::update`
+    NextFrame,
+}
 
 /// Used by game objects to publish (dispatch) GameEvents
 #[derive(Clone, Debug)]
 pub struct EventPublisher {
     events: EventQueue,
+    /// Who publishes through this publisher, for the event trace
+    publisher: Rc<str>,
 }
 
 impl EventPublisher {
     pub fn new(events: EventQueue) -> Self {
-        EventPublisher { events }
+        EventPublisher {
+            events,
+            publisher: "Game".into(),
+        }
+    }
+
+    /// A publisher to the same queue whose events are traced as `publisher`'s
+    pub fn named(&self, publisher: &str) -> Self {
+        EventPublisher {
+            events: self.events.clone(),
+            publisher: publisher.into(),
+        }
     }
 
+    /// Publish `event` for the next frame
     pub fn publish(&self, event: GameEvent) {
-        self.events.borrow_mut().push_back(event);
+        self.publish_in(Phase::NextFrame, event);
+    }
+
+    pub fn publish_in(&self, phase: Phase, event: GameEvent) {
+        self.events
+            .borrow_mut()
+            .push(phase, event, self.publisher.clone());
     }
 }
 
 /// Queue shared between the `EventPublisher` (which is shared among many game objects)
 /// and `Walk`, which deques `GameEvent`s and notifies relevant game objects
-pub type EventQueue = Rc<RefCell<VecDeque<GameEvent>>>;
+pub type EventQueue = Rc<RefCell<Events>>;
+
+/// A `GameEvent` as it was published
+#[derive(Debug)]
+pub struct Published {
+    /// Kinds of the events being dispatched when this one was published,
+    /// outermost first. Empty unless it is part of a cascade.
+    pub causes: Vec<GameEventKind>,
+    pub event: GameEvent,
+    pub phase: Phase,
+    pub publisher: Rc<str>,
+}
+
+/// The `GameEvent`s waiting for each `Phase`
+#[derive(Debug, Default)]
+pub struct Events {
+    cascaded_this_frame: usize,
+    /// The chain of kinds being dispatched, ending with the current event
+    dispatching: Option<Vec<GameEventKind>>,
+    dropped_this_frame: usize,
+    end_of_update: VecDeque<Published>,
+    immediate: VecDeque<Published>,
+    next_frame: VecDeque<Published>,
+}
+
+impl Events {
+    /// Reset the per-frame cascade count
+    pub fn start_frame(&mut self) {
+        self.cascaded_this_frame = 0;
+        self.dropped_this_frame = 0;
+    }
+
+    fn push(&mut self, phase: Phase, event: GameEvent, publisher: Rc<str>) {
+        let queued = Published {
+            causes: self.dispatching.clone().unwrap_or_default(),
+            event,
+            phase,
+            publisher,
+        };
+        match phase {
+            Phase::Immediate => self.immediate.push_back(queued),
+            Phase::EndOfUpdate => self.end_of_update.push_back(queued),
+            Phase::NextFrame => self.next_frame.push_back(queued),
+        }
+    }
+
+    /// Count `queued` against the cascade limit if it is part of a cascade.
+    /// Returns false if it should be dropped.
+    fn admit(&mut self, queued: &Published) -> bool {
+        if queued.causes.is_empty() {
+            return true;
+        }
+
+        self.cascaded_this_frame += 1;
+        if self.cascaded_this_frame <= CASCADE_LIMIT {
+            return true;
+        }
+
+        if self.dropped_this_frame == 0 {
+            let chain: Vec<String> = queued
+                .causes
+                .iter()
+                .chain(std::iter::once(&queued.event.kind()))
+                .map(|kind| format!("{kind:?}"))
+                .collect();
+            error!(
+                "Event cascade limit of {CASCADE_LIMIT} reached this frame, dropping the rest: {}",
+                chain.join(" -> ")
+            );
+        }
+        self.dropped_this_frame += 1;
+
+        false
+    }
+}
+
+/// Dispatch the events queued for `phase`, and any `Phase::Immediate` ones,
+/// through `dispatch`. Immediate events published along the way go next;
+/// events published for `phase` itself wait for its next turn.
+pub fn dispatch_phase(events: &EventQueue, phase: Phase, mut dispatch: impl FnMut(&Published)) {
+    let mut batch = {
+        let mut events = events.borrow_mut();
+        match phase {
+            Phase::Immediate => VecDeque::new(),
+            Phase::EndOfUpdate => mem::take(&mut events.end_of_update),
+            Phase::NextFrame => mem::take(&mut events.next_frame),
+        }
+    };
+
+    loop {
+        let next = {
+            let mut events = events.borrow_mut();
+            events.immediate.pop_front().or_else(|| batch.pop_front())
+        };
+        let Some(queued) = next else {
+            break;
+        };
+        if !events.borrow_mut().admit(&queued) {
+            continue;
+        }
+
+        let mut chain = queued.causes.clone();
+        chain.push(queued.event.kind());
+        events.borrow_mut().dispatching = Some(chain);
+        dispatch(&queued);
+        events.borrow_mut().dispatching = None;
+    }
+}
 
 pub trait EventSubscriber {
     fn name(&self) -> String;
     fn process_event(&mut self, event: &GameEvent);
+    /// The events `process_event` should be called with
+    fn subscriptions(&self) -> Vec<Subscription>;
+    /// A short description of the subscriber's state, so the event trace can
+    /// show what an event changed
+    fn state(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Lets an `Rc<RefCell<Box<dyn Obstacle>>>` be subscribed like any other
+/// subscriber
+impl<T: EventSubscriber + ?Sized> EventSubscriber for Box<T> {
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn process_event(&mut self, event: &GameEvent) {
+        (**self).process_event(event)
+    }
+
+    fn subscriptions(&self) -> Vec<Subscription> {
+        (**self).subscriptions()
+    }
+
+    fn state(&self) -> Option<String> {
+        (**self).state()
+    }
 }
 
 #[derive(Debug)]
 pub enum GameEvent {
+    /// The boy got past a barrier without running into it
+    BoyClearedBarrier {
+        id: String,
+    },
+    BoyFellOffPlatform,
+    /// An obstacle the boy ran into knocks him out
+    BoyHitsObstacle {
+        id: String,
+        /// Name of the segment the obstacle was built for
+        segment: String,
+    },
+    BoyJumped,
+    BoyKnockedOut,
+    /// The boy came down from a jump onto the ground
+    BoyLanded,
+    /// The boy came down from a jump onto a platform
+    BoyLandedOnPlatform {
+        id: String,
+    },
+    /// The boy came down on top of a platform, which holds him up
+    BoyOnPlatform {
+        id: String,
+        platform_top: i16,
+    },
+    BoySlid,
+    DogExitsPlatform,
+    DogHitMark {
+        id: String,
+    },
+    DogOffMark {
+        id: String,
+    },
+    DogLandedOnGround,
+    DogLandedOnPlatform {
+        id: String,
+        platform_top: i16,
+    },
+    DogTooClose,
+    DogTooFar,
+    GameStarted,
+    /// The boy passed the finish line at the end of a level
+    LevelFinished,
+}
+
+impl GameEvent {
+    pub fn kind(&self) -> GameEventKind {
+        match self {
+            GameEvent::BoyClearedBarrier { .. } => GameEventKind::BoyClearedBarrier,
+            GameEvent::BoyFellOffPlatform => GameEventKind::BoyFellOffPlatform,
+            GameEvent::BoyHitsObstacle { .. } => GameEventKind::BoyHitsObstacle,
+            GameEvent::BoyJumped => GameEventKind::BoyJumped,
+            GameEvent::BoyKnockedOut => GameEventKind::BoyKnockedOut,
+            GameEvent::BoyLanded => GameEventKind::BoyLanded,
+            GameEvent::BoyLandedOnPlatform { .. } => GameEventKind::BoyLandedOnPlatform,
+            GameEvent::BoyOnPlatform { .. } => GameEventKind::BoyOnPlatform,
+            GameEvent::BoySlid => GameEventKind::BoySlid,
+            GameEvent::DogExitsPlatform => GameEventKind::DogExitsPlatform,
+            GameEvent::DogHitMark { .. } => GameEventKind::DogHitMark,
+            GameEvent::DogOffMark { .. } => GameEventKind::DogOffMark,
+            GameEvent::DogLandedOnGround => GameEventKind::DogLandedOnGround,
+            GameEvent::DogLandedOnPlatform { .. } => GameEventKind::DogLandedOnPlatform,
+            GameEvent::DogTooClose => GameEventKind::DogTooClose,
+            GameEvent::DogTooFar => GameEventKind::DogTooFar,
+            GameEvent::GameStarted => GameEventKind::GameStarted,
+            GameEvent::LevelFinished => GameEventKind::LevelFinished,
+        }
+    }
+
+    /// Id of the obstacle the event is about, if any
+    pub fn target(&self) -> Option<&str> {
+        match self {
+            GameEvent::BoyClearedBarrier { id }
+            | GameEvent::BoyHitsObstacle { id, .. }
+            | GameEvent::BoyLandedOnPlatform { id }
+            | GameEvent::BoyOnPlatform { id, .. }
+            | GameEvent::DogHitMark { id }
+            | GameEvent::DogOffMark { id }
+            | GameEvent::DogLandedOnPlatform { id, .. } => Some(id),
+            _ => None,
+        }
+    }
+}
+
+/// `GameEvent` variants without their data, for use as lookup keys
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum GameEventKind {
+    BoyClearedBarrier,
+    BoyFellOffPlatform,
     BoyHitsObstacle,
+    BoyJumped,
+    BoyKnockedOut,
+    BoyLanded,
+    BoyLandedOnPlatform,
+    BoyOnPlatform,
+    BoySlid,
     DogExitsPlatform,
-    DogHitMark { id: String },
-    DogOffMark { id: String },
+    DogHitMark,
+    DogOffMark,
     DogLandedOnGround,
-    DogLandedOnPlatform { id: String, platform_top: i16 },
+    DogLandedOnPlatform,
     DogTooClose,
     DogTooFar,
     GameStarted,
+    LevelFinished,
+}
+
+#[cfg(test)]
+impl GameEventKind {
+    pub const ALL: [GameEventKind; 18] = [
+        GameEventKind::BoyClearedBarrier,
+        GameEventKind::BoyFellOffPlatform,
+        GameEventKind::BoyHitsObstacle,
+        GameEventKind::BoyJumped,
+        GameEventKind::BoyKnockedOut,
+        GameEventKind::BoyLanded,
+        GameEventKind::BoyLandedOnPlatform,
+        GameEventKind::BoyOnPlatform,
+        GameEventKind::BoySlid,
+        GameEventKind::DogExitsPlatform,
+        GameEventKind::DogHitMark,
+        GameEventKind::DogOffMark,
+        GameEventKind::DogLandedOnGround,
+        GameEventKind::DogLandedOnPlatform,
+        GameEventKind::DogTooClose,
+        GameEventKind::DogTooFar,
+        GameEventKind::GameStarted,
+        GameEventKind::LevelFinished,
+    ];
+}
+
+/// The kinds of event `handles` is true for, to check a subscriber reacts to
+/// exactly the events it subscribes to
+#[cfg(test)]
+pub fn handled_kinds(handles: impl Fn(&GameEvent) -> bool) -> HashSet<GameEventKind> {
+    GameEventKind::ALL
+        .into_iter()
+        .filter(|kind| handles(&example(*kind)))
+        .collect()
+}
+
+/// An event of `kind` about obstacle "o1"
+#[cfg(test)]
+fn example(kind: GameEventKind) -> GameEvent {
+    let id = "o1".to_string();
+    match kind {
+        GameEventKind::BoyClearedBarrier => GameEvent::BoyClearedBarrier { id },
+        GameEventKind::BoyFellOffPlatform => GameEvent::BoyFellOffPlatform,
+        GameEventKind::BoyHitsObstacle => GameEvent::BoyHitsObstacle {
+            id,
+            segment: "stone".to_string(),
+        },
+        GameEventKind::BoyJumped => GameEvent::BoyJumped,
+        GameEventKind::BoyKnockedOut => GameEvent::BoyKnockedOut,
+        GameEventKind::BoyLanded => GameEvent::BoyLanded,
+        GameEventKind::BoyLandedOnPlatform => GameEvent::BoyLandedOnPlatform { id },
+        GameEventKind::BoyOnPlatform => GameEvent::BoyOnPlatform {
+            id,
+            platform_top: 400,
+        },
+        GameEventKind::BoySlid => GameEvent::BoySlid,
+        GameEventKind::DogExitsPlatform => GameEvent::DogExitsPlatform,
+        GameEventKind::DogHitMark => GameEvent::DogHitMark { id },
+        GameEventKind::DogOffMark => GameEvent::DogOffMark { id },
+        GameEventKind::DogLandedOnGround => GameEvent::DogLandedOnGround,
+        GameEventKind::DogLandedOnPlatform => GameEvent::DogLandedOnPlatform {
+            id,
+            platform_top: 400,
+        },
+        GameEventKind::DogTooClose => GameEvent::DogTooClose,
+        GameEventKind::DogTooFar => GameEvent::DogTooFar,
+        GameEventKind::GameStarted => GameEvent::GameStarted,
+        GameEventKind::LevelFinished => GameEvent::LevelFinished,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_kind_has_an_example_of_that_kind() {
+        let kinds: HashSet<GameEventKind> = GameEventKind::ALL.into_iter().collect();
+
+        assert_eq!(kinds.len(), GameEventKind::ALL.len());
+        for kind in GameEventKind::ALL {
+            assert_eq!(example(kind).kind(), kind);
+        }
+    }
+
+    fn queue() -> (EventQueue, EventPublisher) {
+        let events = Rc::new(RefCell::new(Events::default()));
+        let publisher = EventPublisher::new(events.clone());
+
+        (events, publisher)
+    }
+
+    fn hit_mark(id: &str) -> GameEvent {
+        GameEvent::DogHitMark { id: id.to_string() }
+    }
+
+    #[test]
+    fn each_phase_only_dispatches_its_own_events_and_immediate_ones() {
+        let (events, publisher) = queue();
+        publisher.publish(GameEvent::GameStarted);
+        publisher.publish_in(Phase::EndOfUpdate, GameEvent::DogTooFar);
+        publisher.publish_in(
+            Phase::Immediate,
+            GameEvent::BoyHitsObstacle {
+                id: "b1".to_string(),
+                segment: "stone".to_string(),
+            },
+        );
+
+        let mut dispatched = Vec::new();
+        dispatch_phase(&events, Phase::EndOfUpdate, |published| {
+            dispatched.push(published.event.kind())
+        });
+        assert_eq!(
+            dispatched,
+            vec![GameEventKind::BoyHitsObstacle, GameEventKind::DogTooFar]
+        );
+
+        dispatched.clear();
+        dispatch_phase(&events, Phase::NextFrame, |published| {
+            dispatched.push(published.event.kind())
+        });
+        assert_eq!(dispatched, vec![GameEventKind::GameStarted]);
+    }
+
+    #[test]
+    fn immediate_events_from_subscribers_go_before_the_rest_of_the_phase() {
+        let (events, publisher) = queue();
+        publisher.publish(hit_mark("m1"));
+        publisher.publish(GameEvent::DogTooClose);
+
+        let mut dispatched = Vec::new();
+        dispatch_phase(&events, Phase::NextFrame, |published| {
+            dispatched.push(published.event.kind());
+            if let GameEvent::DogHitMark { id } = &published.event {
+                publisher.publish_in(Phase::Immediate, GameEvent::DogOffMark { id: id.clone() });
+                publisher.publish(GameEvent::DogLandedOnGround);
+            }
+        });
+
+        assert_eq!(
+            dispatched,
+            vec![
+                GameEventKind::DogHitMark,
+                GameEventKind::DogOffMark,
+                GameEventKind::DogTooClose
+            ]
+        );
+        assert_eq!(events.borrow().next_frame.len(), 1);
+        assert_eq!(
+            events.borrow().next_frame[0].causes,
+            vec![GameEventKind::DogHitMark]
+        );
+    }
+
+    #[test]
+    fn endless_cascades_stop_at_the_limit_each_frame() {
+        let (events, publisher) = queue();
+        publisher.publish(hit_mark("m1"));
+
+        let mut dispatched = 0;
+        dispatch_phase(&events, Phase::NextFrame, |published| {
+            dispatched += 1;
+            let next = match &published.event {
+                GameEvent::DogHitMark { id } => GameEvent::DogOffMark { id: id.clone() },
+                _ => hit_mark("m1"),
+            };
+            publisher.publish_in(Phase::Immediate, next);
+        });
+
+        assert_eq!(dispatched, 1 + CASCADE_LIMIT);
+        assert_eq!(events.borrow().dropped_this_frame, 1);
+        assert!(events.borrow().immediate.is_empty());
+
+        events.borrow_mut().start_frame();
+        assert_eq!(events.borrow().dropped_this_frame, 0);
+    }
 }
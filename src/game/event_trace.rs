@@ -0,0 +1,216 @@
+use super::event_queue::{GameEventKind, Phase, Published};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// Most events kept. Older ones are dropped as new ones are recorded.
+pub const TRACE_CAPACITY: usize = 600;
+
+/// A subscriber an event was delivered to
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Delivery {
+    pub subscriber: String,
+    /// The subscriber's state before and after, if the event changed it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transition: Option<(String, String)>,
+}
+
+/// One dispatched `GameEvent`
+#[derive(Clone, Debug, Serialize)]
+pub struct TraceEntry {
+    /// Kinds of the events whose dispatch published this one, outermost first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub causes: Vec<GameEventKind>,
+    pub deliveries: Vec<Delivery>,
+    pub event: String,
+    pub frame: u32,
+    pub phase: Phase,
+    pub publisher: String,
+}
+
+/// Ring buffer of the most recently dispatched events, for debugging. Only
+/// debug builds record anything.
+pub struct EventTrace {
+    capacity: usize,
+    entries: VecDeque<TraceEntry>,
+    frame: u32,
+}
+
+impl Default for EventTrace {
+    fn default() -> Self {
+        EventTrace::new(TRACE_CAPACITY)
+    }
+}
+
+impl EventTrace {
+    pub fn new(capacity: usize) -> Self {
+        EventTrace {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+            frame: 0,
+        }
+    }
+
+    /// Events recorded from now on belong to the next frame
+    pub fn advance_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// The events dispatched in `frame`
+    pub fn entries_in(&self, frame: u32) -> impl Iterator<Item = &TraceEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.frame == frame)
+    }
+
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    /// Frames with at least one recorded event, oldest first
+    pub fn frames(&self) -> Vec<u32> {
+        let mut frames: Vec<u32> = self.entries.iter().map(|entry| entry.frame).collect();
+        frames.dedup();
+
+        frames
+    }
+
+    pub fn record(&mut self, published: &Published, deliveries: Vec<Delivery>) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(TraceEntry {
+            causes: published.causes.clone(),
+            deliveries,
+            event: format!("{:?}", published.event),
+            frame: self.frame,
+            phase: published.phase,
+            publisher: published.publisher.to_string(),
+        });
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.entries)?)
+    }
+}
+
+impl TraceEntry {
+    /// One line for the trace viewer, e.g.
+    /// "EndOfUpdate Dog: DogTooFar -> Dog (Running right => Running left)"
+    pub fn summary(&self) -> String {
+        let deliveries: Vec<String> = self
+            .deliveries
+            .iter()
+            .map(|delivery| match &delivery.transition {
+                Some((before, after)) => {
+                    format!("{} ({before} => {after})", delivery.subscriber)
+                }
+                None => delivery.subscriber.clone(),
+            })
+            .collect();
+
+        format!(
+            "{:?} {}: {} -> {}",
+            self.phase,
+            self.publisher,
+            self.event,
+            if deliveries.is_empty() {
+                "nobody".to_string()
+            } else {
+                deliveries.join(", ")
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::event_queue::GameEvent;
+
+    fn published(event: GameEvent, publisher: &str) -> Published {
+        Published {
+            causes: vec![],
+            event,
+            phase: Phase::NextFrame,
+            publisher: publisher.into(),
+        }
+    }
+
+    #[test]
+    fn keeps_only_the_latest_events() {
+        let mut trace = EventTrace::new(3);
+        for _ in 0..5 {
+            trace.record(&published(GameEvent::DogTooFar, "Dog"), vec![]);
+            trace.advance_frame();
+        }
+
+        assert_eq!(trace.frames(), vec![2, 3, 4]);
+        assert_eq!(trace.entries_in(3).count(), 1);
+        assert_eq!(trace.entries_in(1).count(), 0);
+    }
+
+    #[test]
+    fn summarizes_deliveries_and_transitions() {
+        let mut trace = EventTrace::default();
+        trace.record(
+            &published(GameEvent::DogTooFar, "Dog"),
+            vec![
+                Delivery {
+                    subscriber: "Dog".to_string(),
+                    transition: Some(("Running right".to_string(), "Running left".to_string())),
+                },
+                Delivery {
+                    subscriber: "Score".to_string(),
+                    transition: None,
+                },
+            ],
+        );
+        trace.record(&published(GameEvent::GameStarted, "Game"), vec![]);
+
+        let summaries: Vec<String> = trace.entries_in(0).map(TraceEntry::summary).collect();
+        assert_eq!(
+            summaries,
+            vec![
+                "NextFrame Dog: DogTooFar -> Dog (Running right => Running left), Score",
+                "NextFrame Game: GameStarted -> nobody"
+            ]
+        );
+    }
+
+    #[test]
+    fn exports_the_events_as_json() {
+        let mut trace = EventTrace::default();
+        let mut hit = published(
+            GameEvent::DogOffMark {
+                id: "m1".to_string(),
+            },
+            "m1",
+        );
+        hit.causes = vec![GameEventKind::DogHitMark];
+        hit.phase = Phase::Immediate;
+        trace.advance_frame();
+        trace.record(
+            &hit,
+            vec![Delivery {
+                subscriber: "m1".to_string(),
+                transition: Some(("has dog".to_string(), "empty".to_string())),
+            }],
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&trace.to_json().unwrap()).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!([{
+                "causes": ["DogHitMark"],
+                "deliveries": [{"subscriber": "m1", "transition": ["has dog", "empty"]}],
+                "event": "DogOffMark { id: \"m1\" }",
+                "frame": 1,
+                "phase": "Immediate",
+                "publisher": "m1"
+            }])
+        );
+    }
+}
@@ -1,53 +1,150 @@
+mod editing;
 mod game_over;
+mod level_complete;
+mod level_select;
 pub mod ready;
+mod trace_viewing;
 mod walking;
 
-use super::walk::Walk;
+use super::{editor, walk::Walk};
 use crate::engine::{input::KeyState, renderer::Renderer};
+use editing::Editing;
 use game_over::GameOver;
+use level_complete::LevelComplete;
+use level_select::LevelSelect;
 use ready::Ready;
+use trace_viewing::TraceViewing;
 use walking::Walking;
 
 pub enum WalkTheDogStateMachine {
+    Editing(WalkTheDogState<Editing>),
     Ready(WalkTheDogState<Ready>),
     Walking(WalkTheDogState<Walking>),
     GameOver(WalkTheDogState<GameOver>),
+    LevelComplete(WalkTheDogState<LevelComplete>),
+    LevelSelect(WalkTheDogState<LevelSelect>),
+    TraceViewing(WalkTheDogState<TraceViewing>),
 }
 
 impl WalkTheDogStateMachine {
     pub fn new(walk: Walk) -> Self {
-        WalkTheDogStateMachine::Ready(WalkTheDogState::new(walk))
+        let ready = WalkTheDogState::new(walk);
+
+        if editor::requested_by_url() {
+            WalkTheDogState::<Editing>::open(ready).into()
+        } else {
+            ready.into()
+        }
     }
 
     pub fn draw(&self, renderer: &Renderer) {
         match self {
+            WalkTheDogStateMachine::Editing(state) => state.draw_editor(renderer),
             WalkTheDogStateMachine::GameOver(state) => state.draw(renderer),
-            WalkTheDogStateMachine::Ready(state) => state.draw(renderer),
+            WalkTheDogStateMachine::LevelComplete(state) => state.draw(renderer),
+            WalkTheDogStateMachine::LevelSelect(state) => state.draw_levels(renderer),
+            WalkTheDogStateMachine::Ready(state) => {
+                state.draw(renderer);
+                state.draw_high_scores(renderer);
+            }
+            WalkTheDogStateMachine::TraceViewing(state) => state.draw_trace(renderer),
             WalkTheDogStateMachine::Walking(state) => state.draw(renderer),
         }
     }
 
-    pub fn update(self, keystate: &KeyState) -> Self {
+    pub fn update(mut self, keystate: &KeyState) -> Self {
+        // Runs are sent whatever state the game is in, e.g. on game over
+        self.walk().telemetry().update();
+        if self.moves_walk() {
+            self.walk_mut().start_frame();
+        }
+
         match self {
+            WalkTheDogStateMachine::Editing(state) => state.update(keystate).into(),
             WalkTheDogStateMachine::GameOver(state) => state.update().into(),
+            WalkTheDogStateMachine::LevelComplete(state) => state.update().into(),
+            WalkTheDogStateMachine::LevelSelect(state) => state.update(keystate).into(),
             WalkTheDogStateMachine::Ready(state) => state.update(keystate).into(),
+            WalkTheDogStateMachine::TraceViewing(state) => state.update(keystate).into(),
             WalkTheDogStateMachine::Walking(state) => state.update(keystate).into(),
         }
     }
 }
 
+impl WalkTheDogStateMachine {
+    /// Whether the state's update moves the walk on a frame, as opposed to
+    /// showing a menu, the editor or the trace over a paused one
+    fn moves_walk(&self) -> bool {
+        matches!(
+            self,
+            WalkTheDogStateMachine::GameOver(_)
+                | WalkTheDogStateMachine::LevelComplete(_)
+                | WalkTheDogStateMachine::Ready(_)
+                | WalkTheDogStateMachine::Walking(_)
+        )
+    }
+
+    fn walk(&self) -> &Walk {
+        match self {
+            WalkTheDogStateMachine::Editing(state) => &state.walk,
+            WalkTheDogStateMachine::GameOver(state) => &state.walk,
+            WalkTheDogStateMachine::LevelComplete(state) => &state.walk,
+            WalkTheDogStateMachine::LevelSelect(state) => &state.walk,
+            WalkTheDogStateMachine::Ready(state) => &state.walk,
+            WalkTheDogStateMachine::TraceViewing(state) => &state.walk,
+            WalkTheDogStateMachine::Walking(state) => &state.walk,
+        }
+    }
+
+    fn walk_mut(&mut self) -> &mut Walk {
+        match self {
+            WalkTheDogStateMachine::Editing(state) => &mut state.walk,
+            WalkTheDogStateMachine::GameOver(state) => &mut state.walk,
+            WalkTheDogStateMachine::LevelComplete(state) => &mut state.walk,
+            WalkTheDogStateMachine::LevelSelect(state) => &mut state.walk,
+            WalkTheDogStateMachine::Ready(state) => &mut state.walk,
+            WalkTheDogStateMachine::TraceViewing(state) => &mut state.walk,
+            WalkTheDogStateMachine::Walking(state) => &mut state.walk,
+        }
+    }
+}
+
+impl From<WalkTheDogState<Editing>> for WalkTheDogStateMachine {
+    fn from(state: WalkTheDogState<Editing>) -> Self {
+        WalkTheDogStateMachine::Editing(state)
+    }
+}
+
 impl From<WalkTheDogState<GameOver>> for WalkTheDogStateMachine {
     fn from(state: WalkTheDogState<GameOver>) -> Self {
         WalkTheDogStateMachine::GameOver(state)
     }
 }
 
+impl From<WalkTheDogState<LevelComplete>> for WalkTheDogStateMachine {
+    fn from(state: WalkTheDogState<LevelComplete>) -> Self {
+        WalkTheDogStateMachine::LevelComplete(state)
+    }
+}
+
+impl From<WalkTheDogState<LevelSelect>> for WalkTheDogStateMachine {
+    fn from(state: WalkTheDogState<LevelSelect>) -> Self {
+        WalkTheDogStateMachine::LevelSelect(state)
+    }
+}
+
 impl From<WalkTheDogState<Ready>> for WalkTheDogStateMachine {
     fn from(state: WalkTheDogState<Ready>) -> Self {
         WalkTheDogStateMachine::Ready(state)
     }
 }
 
+impl From<WalkTheDogState<TraceViewing>> for WalkTheDogStateMachine {
+    fn from(state: WalkTheDogState<TraceViewing>) -> Self {
+        WalkTheDogStateMachine::TraceViewing(state)
+    }
+}
+
 impl From<WalkTheDogState<Walking>> for WalkTheDogStateMachine {
     fn from(state: WalkTheDogState<Walking>) -> Self {
         WalkTheDogStateMachine::Walking(state)
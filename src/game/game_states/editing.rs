@@ -0,0 +1,280 @@
+use super::{ready::Ready, walking::Walking, WalkTheDogState, WalkTheDogStateMachine};
+use crate::{
+    browser,
+    engine::{
+        self,
+        input::KeyState,
+        rect::{Point, Rect},
+        renderer::{Renderer, TextOptions},
+    },
+    game::{
+        editor::{Editor, Tool},
+        obstacles::ObstacleVec,
+        segments::definitions::MarkSide,
+    },
+};
+use futures::channel::mpsc::UnboundedReceiver;
+use std::collections::HashSet;
+
+/// Keys that act once per press rather than while held
+const TAPPED_KEYS: [&str; 11] = [
+    "ArrowDown",
+    "ArrowLeft",
+    "ArrowRight",
+    "ArrowUp",
+    "Enter",
+    "Escape",
+    "KeyE",
+    "KeyM",
+    "KeyT",
+    "KeyX",
+    "Space",
+];
+/// Camera scroll per frame while A or D is held
+const PAN_SPEED: i16 = 8;
+/// Name given to exported segments, to be changed in `segments.json`
+const EXPORT_NAME: &str = "new_segment";
+
+const CURSOR_COLOR: &str = "#FF0000";
+const HELP: [&str; 2] = [
+    "Arrows move, A/D scroll, T tool, Space place, X remove",
+    "M marks, E export, Enter play from here, Esc leave",
+];
+const HELP_POSITION: Point = Point { x: 10, y: 20 };
+const HELP_TEXT: TextOptions = TextOptions {
+    color: "#FFFFFF",
+    font: "14px 'Ken Future'",
+};
+const LINE_HEIGHT: i16 = 18;
+
+pub struct Editing {
+    editor: Editor,
+    export_closed: Option<UnboundedReceiver<()>>,
+    /// The editor's obstacles as the game builds them, scrolled to the camera
+    preview: ObstacleVec,
+    previous_keys: HashSet<&'static str>,
+}
+
+impl WalkTheDogState<Editing> {
+    pub fn open(mut state: WalkTheDogState<Ready>) -> Self {
+        let editor = state.walk.open_editor();
+        let preview = state
+            .walk
+            .build_obstacles(editor.obstacles(), -editor.camera_x());
+
+        WalkTheDogState {
+            walk: state.walk,
+            _state: Editing {
+                editor,
+                export_closed: None,
+                preview,
+                previous_keys: HashSet::new(),
+            },
+        }
+    }
+
+    /// The editor's view of the course instead of the walk
+    pub fn draw_editor(&self, renderer: &Renderer) {
+        let editor = &self._state.editor;
+        let camera_x = editor.camera_x();
+
+        self.walk.backgrounds.iter().for_each(|b| b.draw(renderer));
+        self._state
+            .preview
+            .iter()
+            .for_each(|o| o.borrow().draw(renderer));
+        for (side, mark) in editor.mark_previews() {
+            let color = match side {
+                MarkSide::Left => "#000000",
+                MarkSide::Right => "#FFFF00",
+            };
+            renderer.fill_rect(
+                &Rect::new_from_x_y(mark.x() - camera_x, mark.y(), mark.width, mark.height),
+                color,
+            );
+        }
+
+        let cursor = Point {
+            x: editor.cursor().x - camera_x,
+            y: editor.cursor().y,
+        };
+        renderer.fill_rect(
+            &Rect::new_from_x_y(cursor.x - 6, cursor.y, 13, 1),
+            CURSOR_COLOR,
+        );
+        renderer.fill_rect(
+            &Rect::new_from_x_y(cursor.x, cursor.y - 6, 1, 13),
+            CURSOR_COLOR,
+        );
+
+        let tool = match editor.tool() {
+            Tool::Platform(sprite_set) => sprite_set.as_str(),
+            Tool::Stone => "stone",
+        };
+        let status = format!(
+            "Tool {tool}  x {} y {}",
+            editor.cursor().x,
+            editor.cursor().y
+        );
+        for (line, text) in HELP.iter().chain([&status.as_str()]).enumerate() {
+            let position = Point {
+                x: HELP_POSITION.x,
+                y: HELP_POSITION.y + line as i16 * LINE_HEIGHT,
+            };
+            if let Err(err) = renderer.draw_text_ext(text, &position, HELP_TEXT) {
+                error!("Could not draw the editor help {:#?}", err);
+            }
+        }
+    }
+
+    pub fn update(mut self, keystate: &KeyState) -> EditingEndState {
+        let pressed: HashSet<&'static str> = TAPPED_KEYS
+            .into_iter()
+            .filter(|key| keystate.is_pressed(key))
+            .collect();
+        let tapped: HashSet<&'static str> = pressed
+            .difference(&self._state.previous_keys)
+            .copied()
+            .collect();
+        self._state.previous_keys = pressed;
+
+        let export_closed = self
+            ._state
+            .export_closed
+            .as_mut()
+            .is_some_and(|closed| matches!(closed.try_next(), Ok(Some(()))));
+        if export_closed {
+            self.close_export();
+        }
+
+        if tapped.contains("Enter") {
+            return EditingEndState::Play(self.play());
+        }
+        if tapped.contains("Escape") {
+            return EditingEndState::Leave(self.leave());
+        }
+
+        let editor = &mut self._state.editor;
+        let camera_x = editor.camera_x();
+        let mut changed = false;
+        for (key, columns, rows) in [
+            ("ArrowDown", 0, 1),
+            ("ArrowLeft", -1, 0),
+            ("ArrowRight", 1, 0),
+            ("ArrowUp", 0, -1),
+        ] {
+            if tapped.contains(key) {
+                editor.move_cursor(columns, rows);
+            }
+        }
+        if keystate.is_pressed("KeyA") {
+            editor.pan(-PAN_SPEED);
+        }
+        if keystate.is_pressed("KeyD") {
+            editor.pan(PAN_SPEED);
+        }
+        if tapped.contains("KeyT") {
+            editor.next_tool();
+        }
+        if tapped.contains("Space") {
+            editor.place();
+            changed = true;
+        }
+        if tapped.contains("KeyX") {
+            changed |= editor.remove();
+        }
+        if tapped.contains("KeyM") {
+            changed |= editor.cycle_marks();
+        }
+
+        if changed || editor.camera_x() != camera_x {
+            self._state.preview = self.walk.build_obstacles(
+                self._state.editor.obstacles(),
+                -self._state.editor.camera_x(),
+            );
+        }
+        if tapped.contains("KeyE") {
+            self.export();
+        }
+
+        EditingEndState::Continue(self)
+    }
+
+    fn close_export(&mut self) {
+        self._state.export_closed = None;
+        if let Err(err) = browser::hide_ui() {
+            error!("Error hiding the UI overlay {:#?}", err);
+        }
+    }
+
+    /// Show the segment as JSON to paste into `segments.json`
+    fn export(&mut self) {
+        let json = match self._state.editor.export(EXPORT_NAME) {
+            Ok(json) => json,
+            Err(err) => {
+                error!("{:#?}", err);
+                return;
+            }
+        };
+        log!("{json}");
+
+        if self._state.export_closed.is_some() {
+            self.close_export();
+        }
+        self._state.export_closed = browser::draw_ui(&format!(
+            "<div class='editor-export'><textarea readonly>{json}</textarea><button id='close_export'>Close</button></div>"
+        ))
+        .and_then(|_| browser::find_html_element_by_id("close_export"))
+        .map(engine::add_click_handler)
+        .map_err(|err| error!("Could not show the exported segment {:#?}", err))
+        .ok();
+    }
+
+    fn leave(mut self) -> WalkTheDogState<Ready> {
+        if self._state.export_closed.is_some() {
+            self.close_export();
+        }
+        self.walk.close_editor(self._state.editor);
+
+        WalkTheDogState {
+            walk: self.walk,
+            _state: Ready,
+        }
+    }
+
+    /// Run the course as it is on screen, the boy starting from the left edge
+    fn play(mut self) -> WalkTheDogState<Walking> {
+        if self._state.export_closed.is_some() {
+            self.close_export();
+        }
+        let Editing { editor, .. } = self._state;
+        let obstacles = self
+            .walk
+            .build_obstacles(editor.obstacles(), -editor.camera_x());
+        self.walk.play_from(obstacles);
+        self.walk.close_editor(editor);
+
+        WalkTheDogState {
+            walk: self.walk,
+            _state: Ready,
+        }
+        .start_running()
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+pub enum EditingEndState {
+    Continue(WalkTheDogState<Editing>),
+    Leave(WalkTheDogState<Ready>),
+    Play(WalkTheDogState<Walking>),
+}
+
+impl From<EditingEndState> for WalkTheDogStateMachine {
+    fn from(state: EditingEndState) -> Self {
+        match state {
+            EditingEndState::Continue(editing) => editing.into(),
+            EditingEndState::Leave(ready) => ready.into(),
+            EditingEndState::Play(walking) => walking.into(),
+        }
+    }
+}
@@ -55,21 +55,25 @@ mod tests {
     use crate::{
         engine::{
             audio::{Audio, Sound},
+            sfx::{Sfx, SfxConfig},
             sheet::Sheet,
             sprite_sheet::SpriteSheet,
+            storage::MemoryStorage,
         },
         game::{
+            achievements::{AchievementDefinitions, Achievements},
+            difficulty::Difficulty,
             dog::Dog,
-            event_queue::EventPublisher,
-            red_hat_boy::{context::Sfx, RedHatBoy},
+            event_queue::{EventPublisher, Events},
+            levels::Levels,
+            red_hat_boy::RedHatBoy,
+            segments::definitions::SegmentDefinitions,
+            sound_effects::AudioReactions,
+            telemetry::{FetchTransport, Telemetry},
         },
     };
     use futures::channel::mpsc::unbounded;
-    use std::{
-        cell::RefCell,
-        collections::{HashMap, VecDeque},
-        rc::Rc,
-    };
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
     use wasm_bindgen_test::wasm_bindgen_test;
     use web_sys::{AudioBuffer, AudioBufferOptions, HtmlImageElement};
     wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
@@ -83,17 +87,17 @@ mod tests {
         let sound = Sound {
             buffer: AudioBuffer::new(&options).unwrap(),
         };
-        let sfx = Sfx::new(sound.clone(), sound.clone(), sound.clone());
-        let events = Rc::new(RefCell::new(VecDeque::new()));
+        let sfx = Sfx::new(audio);
+        sfx.register("jump", sound, SfxConfig::default());
+        let events = Rc::new(RefCell::new(Events::default()));
         let event_publisher = EventPublisher::new(events.clone());
 
         let boy = RedHatBoy::new(
-            audio,
-            sfx,
             Sheet {
                 frames: HashMap::new(),
             },
             image.clone(),
+            event_publisher.clone(),
         );
         let dog = Dog::new(
             Sheet {
@@ -110,13 +114,26 @@ mod tests {
         );
 
         let walk = Walk::new(
+            Achievements::load(
+                AchievementDefinitions::built_in(),
+                Box::<MemoryStorage>::default(),
+            ),
+            AudioReactions::new(sfx, true),
             image.clone(),
             boy,
+            Difficulty::default(),
             dog,
             event_publisher,
             events,
+            Levels::built_in(),
             image.clone(),
+            SegmentDefinitions::built_in(),
             segment_tiles,
+            Telemetry::load(
+                None,
+                Box::<MemoryStorage>::default(),
+                Box::new(FetchTransport),
+            ),
         );
 
         let document = browser::document().unwrap();
@@ -0,0 +1,79 @@
+use super::{ready::Ready, WalkTheDogState, WalkTheDogStateMachine};
+use crate::{
+    browser,
+    game::walk::{RunMode, Walk},
+};
+use futures::channel::mpsc::UnboundedReceiver;
+
+/// The boy crossed a level's finish line
+pub struct LevelComplete {
+    /// None on the last level
+    pub next_level_event: Option<UnboundedReceiver<()>>,
+    pub replay_event: UnboundedReceiver<()>,
+}
+
+impl LevelComplete {
+    fn next_level_pressed(&mut self) -> bool {
+        self.next_level_event
+            .as_mut()
+            .is_some_and(|event| matches!(event.try_next(), Ok(Some(()))))
+    }
+
+    fn replay_pressed(&mut self) -> bool {
+        matches!(self.replay_event.try_next(), Ok(Some(())))
+    }
+}
+
+impl WalkTheDogState<LevelComplete> {
+    pub fn update(mut self) -> LevelCompleteEndState {
+        if self._state.next_level_pressed() {
+            LevelCompleteEndState::Complete(self.next_level())
+        } else if self._state.replay_pressed() {
+            LevelCompleteEndState::Complete(self.replay())
+        } else {
+            self.walk.update();
+
+            LevelCompleteEndState::Continue(self)
+        }
+    }
+
+    fn next_level(self) -> WalkTheDogState<Ready> {
+        hide_ui();
+        let next = self.walk.level().map_or(0, |(number, _)| number + 1);
+
+        WalkTheDogState {
+            walk: Walk::with_mode(self.walk, RunMode::Level(next)),
+            _state: Ready,
+        }
+    }
+
+    fn replay(self) -> WalkTheDogState<Ready> {
+        hide_ui();
+
+        WalkTheDogState {
+            walk: Walk::reset(self.walk),
+            _state: Ready,
+        }
+    }
+}
+
+fn hide_ui() {
+    if let Err(err) = browser::hide_ui() {
+        error!("Error hiding the UI overlay {:#?}", err);
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+pub enum LevelCompleteEndState {
+    Complete(WalkTheDogState<Ready>),
+    Continue(WalkTheDogState<LevelComplete>),
+}
+
+impl From<LevelCompleteEndState> for WalkTheDogStateMachine {
+    fn from(state: LevelCompleteEndState) -> Self {
+        match state {
+            LevelCompleteEndState::Complete(ready) => ready.into(),
+            LevelCompleteEndState::Continue(level_complete) => level_complete.into(),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use super::{ready::Ready, WalkTheDogState, WalkTheDogStateMachine};
+use crate::{
+    engine::{
+        input::KeyState,
+        rect::Point,
+        renderer::{Renderer, TextOptions},
+    },
+    game::walk::{RunMode, Walk},
+};
+use std::collections::HashSet;
+
+/// Keys that act once per press rather than while held
+const TAPPED_KEYS: [&str; 4] = ["ArrowDown", "ArrowUp", "Enter", "Escape"];
+
+const LIST_POSITION: Point = Point { x: 120, y: 160 };
+const LIST_LINE_HEIGHT: i16 = 30;
+const LIST_TEXT: TextOptions = TextOptions {
+    color: "#FFFFFF",
+    font: "20px 'Ken Future'",
+};
+const SELECTED_TEXT: TextOptions = TextOptions {
+    color: "#FFD700",
+    font: "20px 'Ken Future'",
+};
+const HINT: &str = "Up and down to choose, Enter to select, Esc to go back";
+const HINT_TEXT: TextOptions = TextOptions {
+    color: "#FFD700",
+    font: "14px 'Ken Future'",
+};
+
+pub struct LevelSelect {
+    previous_keys: HashSet<&'static str>,
+    selected: usize,
+}
+
+impl WalkTheDogState<LevelSelect> {
+    pub fn open(state: WalkTheDogState<Ready>) -> Self {
+        let selected = state.walk.level().map_or(0, |(number, _)| number);
+
+        WalkTheDogState {
+            walk: state.walk,
+            _state: LevelSelect {
+                // Whatever opened the screen is still held down
+                previous_keys: TAPPED_KEYS.into_iter().collect(),
+                selected,
+            },
+        }
+    }
+
+    /// Every level with its completions and best time, the selected one highlighted
+    pub fn draw_levels(&self, renderer: &Renderer) {
+        self.walk.backgrounds.iter().for_each(|b| b.draw(renderer));
+
+        let records = &self.walk.level_records;
+        let lines = std::iter::once(("Levels".to_string(), LIST_TEXT)).chain(
+            self.walk
+                .levels()
+                .levels
+                .iter()
+                .enumerate()
+                .map(|(number, level)| {
+                    let completions = records.completions(&level.name);
+                    let status = if completions == 0 {
+                        "not finished".to_string()
+                    } else {
+                        format!("best {}  x{completions}", records.best_time(&level.name))
+                    };
+                    let text = if number == self._state.selected {
+                        SELECTED_TEXT
+                    } else {
+                        LIST_TEXT
+                    };
+
+                    (format!("{}. {}  {status}", number + 1, level.name), text)
+                }),
+        );
+
+        for (row, (line, text)) in lines.enumerate() {
+            let position = Point {
+                x: LIST_POSITION.x,
+                y: LIST_POSITION.y + row as i16 * LIST_LINE_HEIGHT,
+            };
+            if let Err(err) = renderer.draw_text_ext(&line, &position, text) {
+                error!("Could not draw the level list {:#?}", err);
+            }
+        }
+
+        let hint_position = Point {
+            x: LIST_POSITION.x,
+            y: LIST_POSITION.y - LIST_LINE_HEIGHT,
+        };
+        if let Err(err) = renderer.draw_text_ext(HINT, &hint_position, HINT_TEXT) {
+            error!("Could not draw the level select hint {:#?}", err);
+        }
+    }
+
+    pub fn update(mut self, keystate: &KeyState) -> LevelSelectEndState {
+        let pressed: HashSet<&'static str> = TAPPED_KEYS
+            .into_iter()
+            .filter(|key| keystate.is_pressed(key))
+            .collect();
+        let tapped: HashSet<&'static str> = pressed
+            .difference(&self._state.previous_keys)
+            .copied()
+            .collect();
+        self._state.previous_keys = pressed;
+
+        let level_count = self.walk.levels().levels.len();
+        if tapped.contains("ArrowDown") && self._state.selected + 1 < level_count {
+            self._state.selected += 1;
+        }
+        if tapped.contains("ArrowUp") {
+            self._state.selected = self._state.selected.saturating_sub(1);
+        }
+
+        if tapped.contains("Enter") && level_count > 0 {
+            let mode = RunMode::Level(self._state.selected);
+            LevelSelectEndState::Complete(self.back(mode))
+        } else if tapped.contains("Escape") {
+            let mode = self.walk.mode();
+            LevelSelectEndState::Complete(self.back(mode))
+        } else {
+            LevelSelectEndState::Continue(self)
+        }
+    }
+
+    /// Back to the `Ready` screen, set up for `mode`
+    fn back(self, mode: RunMode) -> WalkTheDogState<Ready> {
+        WalkTheDogState {
+            walk: Walk::with_mode(self.walk, mode),
+            _state: Ready,
+        }
+    }
+}
+
+pub enum LevelSelectEndState {
+    Complete(WalkTheDogState<Ready>),
+    Continue(WalkTheDogState<LevelSelect>),
+}
+
+impl From<LevelSelectEndState> for WalkTheDogStateMachine {
+    fn from(state: LevelSelectEndState) -> Self {
+        match state {
+            LevelSelectEndState::Complete(ready) => ready.into(),
+            LevelSelectEndState::Continue(level_select) => level_select.into(),
+        }
+    }
+}
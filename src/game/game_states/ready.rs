@@ -1,7 +1,26 @@
-use super::{walking::Walking, WalkTheDogState, WalkTheDogStateMachine};
+use super::{
+    editing::Editing, level_select::LevelSelect, walking::Walking, WalkTheDogState,
+    WalkTheDogStateMachine,
+};
 use crate::{
-    engine::input::KeyState,
-    game::{event_queue::GameEvent, walk::Walk},
+    browser,
+    engine::{
+        input::KeyState,
+        rect::Point,
+        renderer::{Renderer, TextOptions},
+    },
+    game::{
+        event_queue::GameEvent,
+        walk::{RunMode, Walk},
+    },
+};
+
+const MENU_HINT: &str = "Press S to select a level, L for the editor";
+const MENU_HINT_POSITION: Point = Point { x: 170, y: 140 };
+const MODE_POSITION: Point = Point { x: 170, y: 160 };
+const MODE_TEXT: TextOptions = TextOptions {
+    color: "#FFD700",
+    font: "16px 'Ken Future'",
 };
 
 pub struct Ready;
@@ -14,11 +33,50 @@ impl WalkTheDogState<Ready> {
         }
     }
 
+    /// The selected mode's results: the high score table, today's daily run
+    /// or the level's records
+    pub fn draw_high_scores(&self, renderer: &Renderer) {
+        let hint = match self.walk.mode() {
+            RunMode::Daily => {
+                self.walk.daily_run.draw(renderer, &browser::today());
+                "Press E for Endless"
+            }
+            RunMode::Endless => {
+                self.walk.high_scores.draw(renderer);
+                "Press D for the Daily Run"
+            }
+            RunMode::Level(_) => {
+                if let Some((number, level)) = self.walk.level() {
+                    self.walk.level_records.draw(renderer, number, level);
+                }
+                "Press E for Endless"
+            }
+        };
+
+        if let Err(err) = renderer.draw_text_ext(hint, &MODE_POSITION, MODE_TEXT) {
+            error!("Could not draw the mode hint {:#?}", err);
+        }
+        if let Err(err) = renderer.draw_text_ext(MENU_HINT, &MENU_HINT_POSITION, MODE_TEXT) {
+            error!("Could not draw the menu hint {:#?}", err);
+        }
+    }
+
     pub fn update(mut self, keystate: &KeyState) -> ReadyEndState {
-        self.walk.boy.update();
+        self.walk.boy().update();
         self.walk.update();
 
-        if keystate.is_pressed("ArrowRight") {
+        if keystate.is_pressed("KeyD") {
+            self.walk = Walk::with_mode(self.walk, RunMode::Daily);
+        }
+        if keystate.is_pressed("KeyE") {
+            self.walk = Walk::with_mode(self.walk, RunMode::Endless);
+        }
+
+        if keystate.is_pressed("KeyS") {
+            ReadyEndState::SelectLevel(WalkTheDogState::<LevelSelect>::open(self))
+        } else if keystate.is_pressed("KeyL") {
+            ReadyEndState::Edit(WalkTheDogState::<Editing>::open(self))
+        } else if keystate.is_pressed("ArrowRight") {
             ReadyEndState::Complete(self.start_running())
         } else {
             ReadyEndState::Continue(self)
@@ -26,11 +84,12 @@ impl WalkTheDogState<Ready> {
     }
 
     fn run_right(&mut self) {
-        self.walk.boy.run_right();
+        self.walk.boy().run_right();
+        self.walk.score().start();
         self.walk.event_publisher.publish(GameEvent::GameStarted);
     }
 
-    fn start_running(mut self) -> WalkTheDogState<Walking> {
+    pub fn start_running(mut self) -> WalkTheDogState<Walking> {
         self.run_right();
 
         WalkTheDogState {
@@ -41,6 +100,8 @@ impl WalkTheDogState<Ready> {
 }
 
 pub enum ReadyEndState {
+    Edit(WalkTheDogState<Editing>),
+    SelectLevel(WalkTheDogState<LevelSelect>),
     Complete(WalkTheDogState<Walking>),
     Continue(WalkTheDogState<Ready>),
 }
@@ -48,6 +109,8 @@ pub enum ReadyEndState {
 impl From<ReadyEndState> for WalkTheDogStateMachine {
     fn from(state: ReadyEndState) -> Self {
         match state {
+            ReadyEndState::Edit(editing) => editing.into(),
+            ReadyEndState::SelectLevel(level_select) => level_select.into(),
             ReadyEndState::Complete(walking) => walking.into(),
             ReadyEndState::Continue(ready) => ready.into(),
         }
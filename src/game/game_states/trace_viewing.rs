@@ -0,0 +1,199 @@
+use super::{walking::Walking, WalkTheDogState, WalkTheDogStateMachine};
+use crate::{
+    browser,
+    engine::{
+        self,
+        input::KeyState,
+        rect::{Point, Rect},
+        renderer::{Renderer, TextOptions},
+    },
+    game::{event_trace::TraceEntry, HEIGHT, WIDTH},
+};
+use futures::channel::mpsc::UnboundedReceiver;
+use std::collections::HashSet;
+
+/// Keys that act once per press rather than while held
+const TAPPED_KEYS: [&str; 4] = ["ArrowLeft", "ArrowRight", "Escape", "KeyJ"];
+/// How far back the viewer goes
+const VIEW_FRAMES: u32 = 5 * 60;
+const FRAMES_PER_SECOND: f32 = 60.0;
+
+const PANEL_COLOR: &str = "rgba(0, 0, 0, 0.75)";
+const HELP: &str = "Left/Right scrub, J export JSON, Esc resume";
+const TEXT_POSITION: Point = Point { x: 10, y: 20 };
+const TITLE_TEXT: TextOptions = TextOptions {
+    color: "#FFD700",
+    font: "14px 'Ken Future'",
+};
+const ENTRY_TEXT: TextOptions = TextOptions {
+    color: "#FFFFFF",
+    font: "12px monospace",
+};
+const LINE_HEIGHT: i16 = 16;
+
+/// The game paused on the debug overlay, scrubbing through recent events
+pub struct TraceViewing {
+    export_closed: Option<UnboundedReceiver<()>>,
+    previous_keys: HashSet<&'static str>,
+    /// Index into `frames`
+    selected: usize,
+    /// Recent frames with events, oldest first
+    frames: Vec<u32>,
+}
+
+impl WalkTheDogState<TraceViewing> {
+    pub fn open(state: WalkTheDogState<Walking>) -> Self {
+        let trace = state.walk.event_trace();
+        let oldest = trace.frame().saturating_sub(VIEW_FRAMES);
+        let frames: Vec<u32> = trace
+            .frames()
+            .into_iter()
+            .filter(|frame| *frame >= oldest)
+            .collect();
+
+        WalkTheDogState {
+            walk: state.walk,
+            _state: TraceViewing {
+                export_closed: None,
+                // Whatever opened the viewer is still held down
+                previous_keys: TAPPED_KEYS.into_iter().collect(),
+                selected: frames.len().saturating_sub(1),
+                frames,
+            },
+        }
+    }
+
+    /// The paused walk under the selected frame's events
+    pub fn draw_trace(&self, renderer: &Renderer) {
+        self.walk.draw(renderer);
+        renderer.fill_rect(&Rect::new_from_x_y(0, 0, WIDTH, HEIGHT), PANEL_COLOR);
+
+        let trace = self.walk.event_trace();
+        let (title, entries): (String, Vec<&TraceEntry>) =
+            match self._state.frames.get(self._state.selected) {
+                Some(frame) => (
+                    format!(
+                        "Frame {frame} ({:.1}s ago)  {} of {}",
+                        (trace.frame() - frame) as f32 / FRAMES_PER_SECOND,
+                        self._state.selected + 1,
+                        self._state.frames.len()
+                    ),
+                    trace.entries_in(*frame).collect(),
+                ),
+                None => ("No events in the last few seconds".to_string(), vec![]),
+            };
+
+        let lines = [(HELP.to_string(), TITLE_TEXT), (title, TITLE_TEXT)]
+            .into_iter()
+            .chain(entries.iter().flat_map(|entry| {
+                let causes = (!entry.causes.is_empty()).then(|| {
+                    let causes: Vec<String> = entry
+                        .causes
+                        .iter()
+                        .map(|kind| format!("{kind:?}"))
+                        .collect();
+                    (format!("  caused by {}", causes.join(" -> ")), ENTRY_TEXT)
+                });
+                std::iter::once((entry.summary(), ENTRY_TEXT)).chain(causes)
+            }));
+        for (line, (text, options)) in lines.enumerate() {
+            let position = Point {
+                x: TEXT_POSITION.x,
+                y: TEXT_POSITION.y + line as i16 * LINE_HEIGHT,
+            };
+            if let Err(err) = renderer.draw_text_ext(&text, &position, options) {
+                error!("Could not draw the event trace {:#?}", err);
+            }
+        }
+    }
+
+    pub fn update(mut self, keystate: &KeyState) -> TraceViewingEndState {
+        let pressed: HashSet<&'static str> = TAPPED_KEYS
+            .into_iter()
+            .filter(|key| keystate.is_pressed(key))
+            .collect();
+        let tapped: HashSet<&'static str> = pressed
+            .difference(&self._state.previous_keys)
+            .copied()
+            .collect();
+        self._state.previous_keys = pressed;
+
+        let export_closed = self
+            ._state
+            .export_closed
+            .as_mut()
+            .is_some_and(|closed| matches!(closed.try_next(), Ok(Some(()))));
+        if export_closed {
+            self.close_export();
+        }
+
+        if tapped.contains("Escape") {
+            return TraceViewingEndState::Complete(self.resume());
+        }
+        if tapped.contains("ArrowLeft") {
+            self._state.selected = self._state.selected.saturating_sub(1);
+        }
+        if tapped.contains("ArrowRight") && self._state.selected + 1 < self._state.frames.len() {
+            self._state.selected += 1;
+        }
+        if tapped.contains("KeyJ") {
+            self.export();
+        }
+
+        TraceViewingEndState::Continue(self)
+    }
+
+    fn close_export(&mut self) {
+        self._state.export_closed = None;
+        if let Err(err) = browser::hide_ui() {
+            error!("Error hiding the UI overlay {:#?}", err);
+        }
+    }
+
+    /// Show the whole trace as JSON
+    fn export(&mut self) {
+        let json = match self.walk.event_trace().to_json() {
+            Ok(json) => json,
+            Err(err) => {
+                error!("Could not export the event trace {:#?}", err);
+                return;
+            }
+        };
+
+        if self._state.export_closed.is_some() {
+            self.close_export();
+        }
+        self._state.export_closed = browser::draw_ui(&format!(
+            "<div class='trace-export'><textarea readonly>{json}</textarea><button id='close_trace'>Close</button></div>"
+        ))
+        .and_then(|_| browser::find_html_element_by_id("close_trace"))
+        .map(engine::add_click_handler)
+        .map_err(|err| error!("Could not show the event trace {:#?}", err))
+        .ok();
+    }
+
+    fn resume(mut self) -> WalkTheDogState<Walking> {
+        if self._state.export_closed.is_some() {
+            self.close_export();
+        }
+
+        WalkTheDogState {
+            walk: self.walk,
+            _state: Walking,
+        }
+    }
+}
+
+pub enum TraceViewingEndState {
+    Complete(WalkTheDogState<Walking>),
+    Continue(WalkTheDogState<TraceViewing>),
+}
+
+impl From<TraceViewingEndState> for WalkTheDogStateMachine {
+    fn from(state: TraceViewingEndState) -> Self {
+        match state {
+            TraceViewingEndState::Complete(walking) => walking.into(),
+            TraceViewingEndState::Continue(trace_viewing) => trace_viewing.into(),
+        }
+    }
+}
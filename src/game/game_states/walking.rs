@@ -1,7 +1,11 @@
-use super::{game_over::GameOver, WalkTheDogState, WalkTheDogStateMachine};
+use super::{
+    game_over::GameOver, level_complete::LevelComplete, trace_viewing::TraceViewing,
+    WalkTheDogState, WalkTheDogStateMachine,
+};
 use crate::{
     browser,
     engine::{self, input::KeyState},
+    game::{high_scores::HighScore, levels, walk::RunMode},
 };
 
 pub struct Walking;
@@ -10,15 +14,19 @@ const TIMELINE_MINIMUM: i16 = 1000;
 
 impl WalkTheDogState<Walking> {
     pub fn update(mut self, keystate: &KeyState) -> WalkingEndState {
+        if cfg!(debug_assertions) && keystate.is_pressed("Backquote") {
+            return WalkingEndState::Trace(WalkTheDogState::<TraceViewing>::open(self));
+        }
         if keystate.is_pressed("Space") {
-            self.walk.boy.jump();
+            self.walk.boy().jump();
         }
         if keystate.is_pressed("ArrowDown") {
-            self.walk.boy.slide();
+            self.walk.boy().slide();
         }
 
-        self.walk.boy.update();
+        self.walk.boy().update();
         let walking_speed = self.walk.velocity();
+        self.walk.score().advance(-walking_speed);
 
         let [first_background, second_background] = &mut self.walk.backgrounds;
         first_background.move_horizontally(walking_speed);
@@ -32,10 +40,7 @@ impl WalkTheDogState<Walking> {
 
         self.walk.drop_surpassed_obstacles();
 
-        for obstacle in self.walk.obstacles.iter() {
-            obstacle.borrow_mut().move_horizontally(walking_speed);
-            obstacle.borrow_mut().check_intersection(&mut self.walk.boy);
-        }
+        self.walk.move_obstacles(walking_speed);
         self.walk.update();
 
         if self.walk.timeline < TIMELINE_MINIMUM {
@@ -44,22 +49,116 @@ impl WalkTheDogState<Walking> {
             self.walk.timeline += walking_speed;
         }
 
-        if self.walk.knocked_out() {
+        if self.walk.level_finished() {
+            WalkingEndState::Won(self.win())
+        } else if self.walk.knocked_out() {
             WalkingEndState::Complete(self.end_game())
         } else {
             WalkingEndState::Continue(self)
         }
     }
 
-    fn end_game(self) -> WalkTheDogState<GameOver> {
-        let new_game_event = browser::draw_ui("<button id='new_game'>New Game</button>")
-            .and_then(|_| browser::find_html_element_by_id("new_game"))
+    fn end_game(mut self) -> WalkTheDogState<GameOver> {
+        self.walk.score().stop();
+        self.report_run();
+        let stats = self.walk.score().stats();
+        let date = browser::today();
+        let results = match self.walk.mode() {
+            RunMode::Daily => {
+                let new_best = self.walk.daily_run.record(&date, stats.score);
+                self.walk.daily_run.summary_html(&date, new_best)
+            }
+            RunMode::Endless => {
+                let rank = self.walk.high_scores.record(HighScore {
+                    date,
+                    meters: stats.meters,
+                    score: stats.score,
+                    seed: Some(self.walk.seed()),
+                });
+                self.walk.high_scores.table_html(rank)
+            }
+            RunMode::Level(_) => self
+                .walk
+                .level()
+                .map(|(_, level)| self.walk.level_records.summary_html(level))
+                .unwrap_or_default(),
+        };
+        let new_game_event = browser::draw_ui(&format!(
+            "<div class='run-summary'>{}<p class='seed'>Seed {}</p>{}{}<button id='new_game'>New Game</button></div>",
+            stats.summary_html(),
+            self.walk.seed(),
+            results,
+            self.walk.achievements().summary_html()
+        ))
+        .and_then(|_| browser::find_html_element_by_id("new_game"))
+        .map(engine::add_click_handler)
+        .unwrap();
+
+        WalkTheDogState {
+            walk: self.walk,
+            _state: GameOver { new_game_event },
+        }
+    }
+
+    /// Hand the finished run to telemetry, if the player opted in
+    fn report_run(&self) {
+        let score = self.walk.score();
+        self.walk.telemetry().end_run(
+            &score.stats(),
+            score.frames(),
+            self.walk.seed(),
+            &self.walk.mode_name(),
+        );
+    }
+
+    /// Record the level's time and offer the next level or a replay
+    fn win(mut self) -> WalkTheDogState<LevelComplete> {
+        self.walk.score().stop();
+        self.report_run();
+        let frames = self.walk.score().frames();
+        let (number, name) = match self.walk.level() {
+            Some((number, level)) => (number, level.name.clone()),
+            None => (0, String::new()),
+        };
+        let new_best = self.walk.level_records.record(&name, frames);
+        let has_next_level = number + 1 < self.walk.levels().levels.len();
+
+        let callout = if new_best {
+            "<p class='new-best'>New best time!</p>"
+        } else {
+            ""
+        };
+        let next_button = if has_next_level {
+            "<button id='next_level'>Next</button>"
+        } else {
+            ""
+        };
+        let level = self
+            .walk
+            .level()
+            .map(|(_, level)| self.walk.level_records.summary_html(level))
+            .unwrap_or_default();
+        let shown = browser::draw_ui(&format!(
+            "<div class='run-summary'><h2>Level complete</h2><p>{name} in {}</p>{callout}{level}{next_button}<button id='replay'>Again</button></div>",
+            levels::format_time(frames)
+        ));
+
+        let replay_event = shown
+            .and_then(|_| browser::find_html_element_by_id("replay"))
             .map(engine::add_click_handler)
             .unwrap();
+        let next_level_event = has_next_level.then(|| {
+            browser::find_html_element_by_id("next_level")
+                .map(engine::add_click_handler)
+                .unwrap()
+        });
 
         WalkTheDogState {
             walk: self.walk,
-            _state: GameOver { new_game_event },
+            _state: LevelComplete {
+                next_level_event,
+                replay_event,
+            },
         }
     }
 }
@@ -67,6 +166,8 @@ impl WalkTheDogState<Walking> {
 pub enum WalkingEndState {
     Complete(WalkTheDogState<GameOver>),
     Continue(WalkTheDogState<Walking>),
+    Trace(WalkTheDogState<TraceViewing>),
+    Won(WalkTheDogState<LevelComplete>),
 }
 
 impl From<WalkingEndState> for WalkTheDogStateMachine {
@@ -74,6 +175,8 @@ impl From<WalkingEndState> for WalkTheDogStateMachine {
         match state {
             WalkingEndState::Complete(game_over) => game_over.into(),
             WalkingEndState::Continue(walking) => walking.into(),
+            WalkingEndState::Trace(trace_viewing) => trace_viewing.into(),
+            WalkingEndState::Won(level_complete) => level_complete.into(),
         }
     }
 }
@@ -0,0 +1,206 @@
+use crate::engine::{
+    rect::Point,
+    renderer::{Renderer, TextOptions},
+    storage::Storage,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+const MAX_HIGH_SCORES: usize = 5;
+const STORAGE_KEY: &str = "walk_the_dog.high_scores";
+
+const TABLE_POSITION: Point = Point { x: 170, y: 200 };
+const TABLE_LINE_HEIGHT: i16 = 30;
+const TABLE_TEXT: TextOptions = TextOptions {
+    color: "#FFFFFF",
+    font: "20px 'Ken Future'",
+};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct HighScore {
+    /// UTC date of the run as YYYY-MM-DD
+    pub date: String,
+    pub meters: i32,
+    pub score: i32,
+    /// Seed the run's course was generated from, if known
+    pub seed: Option<u64>,
+}
+
+/// The best `MAX_HIGH_SCORES` runs, best first, saved to `Storage` on every change
+pub struct HighScores {
+    entries: Vec<HighScore>,
+    storage: Box<dyn Storage>,
+}
+
+impl HighScores {
+    /// Load the saved table. A missing or unreadable table starts out empty.
+    pub fn load(storage: Box<dyn Storage>) -> Self {
+        let entries = storage
+            .get(STORAGE_KEY)
+            .and_then(|json| match serde_json::from_str(&json) {
+                Ok(entries) => Some(entries),
+                Err(err) => {
+                    error!("Ignoring unreadable high scores {:#?}", err);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        HighScores { entries, storage }
+    }
+
+    pub fn draw(&self, renderer: &Renderer) {
+        let lines = std::iter::once("High Scores".to_string()).chain(
+            self.entries.iter().enumerate().map(|(i, entry)| {
+                format!(
+                    "{}. {}  {}m  {}",
+                    i + 1,
+                    entry.score,
+                    entry.meters,
+                    entry.date
+                )
+            }),
+        );
+
+        for (row, line) in lines.enumerate() {
+            let position = Point {
+                x: TABLE_POSITION.x,
+                y: TABLE_POSITION.y + row as i16 * TABLE_LINE_HEIGHT,
+            };
+            if let Err(err) = renderer.draw_text_ext(&line, &position, TABLE_TEXT) {
+                error!("Could not draw high scores {:#?}", err);
+            }
+        }
+    }
+
+    /// Add a run to the table if it ranks. Returns its rank (0 is best), or
+    /// None if it did not make the table.
+    pub fn record(&mut self, high_score: HighScore) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|entry| high_score.score > entry.score)
+            .unwrap_or(self.entries.len());
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+
+        self.entries.insert(rank, high_score);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        if let Err(err) = self.save() {
+            error!("Could not save high scores {:#?}", err);
+        }
+
+        Some(rank)
+    }
+
+    /// HTML table of the high scores, highlighting the entry at `rank` (the
+    /// run that just ended) with a "new best!" callout when it tops the table
+    pub fn table_html(&self, rank: Option<usize>) -> String {
+        let callout = if rank == Some(0) {
+            "<p class='new-best'>New best!</p>"
+        } else {
+            ""
+        };
+        let rows: Vec<String> = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let class = if rank == Some(i) {
+                    " class='latest'"
+                } else {
+                    ""
+                };
+                let seed = entry.seed.map(|seed| seed.to_string()).unwrap_or_default();
+                format!(
+                    "<tr{class}><td>{}</td><td>{}</td><td>{}m</td><td>{}</td><td>{seed}</td></tr>",
+                    i + 1,
+                    entry.score,
+                    entry.meters,
+                    entry.date
+                )
+            })
+            .collect();
+
+        format!(
+            "{callout}<table class='high-scores'>{}</table>",
+            rows.join("")
+        )
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let json = serde_json::to_string(&self.entries)?;
+
+        self.storage.set(STORAGE_KEY, &json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::storage::MemoryStorage;
+
+    fn run(score: i32) -> HighScore {
+        HighScore {
+            date: "2026-10-19".to_string(),
+            meters: score,
+            score,
+            seed: Some(42),
+        }
+    }
+
+    #[test]
+    fn keeps_the_best_runs_best_first() {
+        let mut high_scores = HighScores::load(Box::<MemoryStorage>::default());
+        for score in [30, 10, 50, 20, 40, 60, 5] {
+            high_scores.record(run(score));
+        }
+
+        let scores: Vec<i32> = high_scores.entries.iter().map(|e| e.score).collect();
+        assert_eq!(scores, vec![60, 50, 40, 30, 20]);
+    }
+
+    #[test]
+    fn record_returns_the_rank_of_runs_that_make_the_table() {
+        let mut high_scores = HighScores::load(Box::<MemoryStorage>::default());
+
+        assert_eq!(high_scores.record(run(10)), Some(0));
+        assert_eq!(high_scores.record(run(5)), Some(1));
+        assert_eq!(high_scores.record(run(20)), Some(0));
+        (0..3).for_each(|_| {
+            high_scores.record(run(30));
+        });
+        assert_eq!(high_scores.record(run(1)), None);
+    }
+
+    #[test]
+    fn high_scores_survive_reloading_from_storage() {
+        let mut storage = MemoryStorage::default();
+        let json = serde_json::to_string(&vec![run(10)]).unwrap();
+        storage.set(STORAGE_KEY, &json).unwrap();
+
+        let high_scores = HighScores::load(Box::new(storage));
+
+        assert_eq!(high_scores.entries, vec![run(10)]);
+    }
+
+    #[test]
+    fn unreadable_storage_starts_an_empty_table() {
+        let mut storage = MemoryStorage::default();
+        storage.set(STORAGE_KEY, "not json").unwrap();
+
+        assert!(HighScores::load(Box::new(storage)).entries.is_empty());
+    }
+
+    #[test]
+    fn new_best_is_called_out() {
+        let mut high_scores = HighScores::load(Box::<MemoryStorage>::default());
+        high_scores.record(run(10));
+        let rank = high_scores.record(run(5));
+
+        assert!(!high_scores.table_html(rank).contains("New best!"));
+        let rank = high_scores.record(run(20));
+        assert!(high_scores.table_html(rank).contains("New best!"));
+    }
+}
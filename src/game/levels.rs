@@ -0,0 +1,264 @@
+use super::segments::definitions::SegmentDefinitions;
+use crate::{
+    browser,
+    engine::{
+        rect::Point,
+        renderer::{Renderer, TextOptions},
+        storage::Storage,
+    },
+};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const LEVELS_CONFIG: &str = "levels.json";
+const STORAGE_KEY: &str = "walk_the_dog.levels";
+const FRAMES_PER_SECOND: u32 = 60;
+
+const PANEL_POSITION: Point = Point { x: 170, y: 200 };
+const PANEL_LINE_HEIGHT: i16 = 30;
+const PANEL_TEXT: TextOptions = TextOptions {
+    color: "#FFFFFF",
+    font: "20px 'Ken Future'",
+};
+
+/// The hand-authored levels, as loaded from `LEVELS_CONFIG`
+#[derive(Clone, Debug, Deserialize)]
+pub struct Levels {
+    pub levels: Vec<Level>,
+}
+
+/// A finite course: its segments in order, then the finish line
+#[derive(Clone, Debug, Deserialize)]
+pub struct Level {
+    pub name: String,
+    /// Names of segments from `segments.json`
+    pub segments: Vec<String>,
+}
+
+impl Levels {
+    /// Load and check the levels at `json_path` against `definitions`, falling
+    /// back to the levels built into the game if they can't be used
+    pub async fn load(json_path: &str, definitions: &SegmentDefinitions) -> Self {
+        match Levels::fetch(json_path, definitions).await {
+            Ok(levels) => levels,
+            Err(err) => {
+                error!("Using the built-in levels {:#?}", err);
+                Levels::built_in()
+            }
+        }
+    }
+
+    /// The levels shipped with the game
+    pub fn built_in() -> Self {
+        serde_json::from_str(include_str!("../../static/levels.json"))
+            .expect("Built-in levels should deserialize")
+    }
+
+    pub fn get(&self, level: usize) -> Option<&Level> {
+        self.levels.get(level)
+    }
+
+    /// Check every level has segments and they are all defined
+    pub fn validate(&self, definitions: &SegmentDefinitions) -> Result<()> {
+        let mut problems = Vec::new();
+        for level in &self.levels {
+            if level.segments.is_empty() {
+                problems.push(format!("{} has no segments", level.name));
+            }
+            for segment in &level.segments {
+                if definitions.index_of(segment).is_none() {
+                    problems.push(format!("{} uses undefined segment {}", level.name, segment));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("Level validation failed\n{}", problems.join("\n")))
+        }
+    }
+
+    async fn fetch(json_path: &str, definitions: &SegmentDefinitions) -> Result<Self> {
+        let json = browser::fetch_json(json_path).await?;
+        let levels = serde_wasm_bindgen::from_value::<Levels>(json)
+            .map_err(|err| anyhow!("Error deserializing {} {:#?}", json_path, err))?;
+        levels.validate(definitions)?;
+
+        Ok(levels)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+struct LevelResult {
+    /// Fewest frames taken to reach the finish line
+    best_frames: Option<u32>,
+    completions: u32,
+}
+
+/// Completions and best times per level name, saved to `Storage` after every
+/// completed level
+pub struct LevelRecords {
+    results: BTreeMap<String, LevelResult>,
+    storage: Box<dyn Storage>,
+}
+
+impl LevelRecords {
+    /// Load the saved records. Missing or unreadable records start out empty.
+    pub fn load(storage: Box<dyn Storage>) -> Self {
+        let results = storage
+            .get(STORAGE_KEY)
+            .and_then(|json| match serde_json::from_str(&json) {
+                Ok(results) => Some(results),
+                Err(err) => {
+                    error!("Ignoring unreadable level records {:#?}", err);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        LevelRecords { results, storage }
+    }
+
+    /// Fewest frames `level` has been finished in
+    pub fn best_frames(&self, level: &str) -> Option<u32> {
+        self.results
+            .get(level)
+            .and_then(|result| result.best_frames)
+    }
+
+    pub fn completions(&self, level: &str) -> u32 {
+        self.results
+            .get(level)
+            .map_or(0, |result| result.completions)
+    }
+
+    /// Panel for the `Ready` screen with the selected level's records
+    pub fn draw(&self, renderer: &Renderer, number: usize, level: &Level) {
+        let lines = [
+            format!("Level {} {}", number + 1, level.name),
+            format!("Best time {}", self.best_time(&level.name)),
+            format!("Completed {} times", self.completions(&level.name)),
+        ];
+
+        for (row, line) in lines.iter().enumerate() {
+            let position = Point {
+                x: PANEL_POSITION.x,
+                y: PANEL_POSITION.y + row as i16 * PANEL_LINE_HEIGHT,
+            };
+            if let Err(err) = renderer.draw_text_ext(line, &position, PANEL_TEXT) {
+                error!("Could not draw the level records {:#?}", err);
+            }
+        }
+    }
+
+    /// `level`'s best time for display, or "-" if it was never finished
+    pub fn best_time(&self, level: &str) -> String {
+        self.best_frames(level).map_or("-".to_string(), format_time)
+    }
+
+    /// Count a completion of `level` that took `frames`. Returns true if it
+    /// is the best time so far.
+    pub fn record(&mut self, level: &str, frames: u32) -> bool {
+        let result = self.results.entry(level.to_string()).or_default();
+        let new_best = result.best_frames.map_or(true, |best| frames < best);
+        result.completions += 1;
+        if new_best {
+            result.best_frames = Some(frames);
+        }
+
+        if let Err(err) = self.save() {
+            error!("Could not save level records {:#?}", err);
+        }
+
+        new_best
+    }
+
+    pub fn summary_html(&self, level: &Level) -> String {
+        format!(
+            "<dl class='level'>\
+            <dt>Level</dt><dd>{}</dd>\
+            <dt>Best time</dt><dd>{}</dd>\
+            <dt>Completed</dt><dd>{} times</dd>\
+            </dl>",
+            level.name,
+            self.best_time(&level.name),
+            self.completions(&level.name)
+        )
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let json = serde_json::to_string(&self.results)?;
+
+        self.storage.set(STORAGE_KEY, &json)
+    }
+}
+
+/// Seconds to a tenth, e.g. "12.5s"
+pub fn format_time(frames: u32) -> String {
+    let tenths = frames * 10 / FRAMES_PER_SECOND;
+
+    format!("{}.{}s", tenths / 10, tenths % 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::storage::MemoryStorage;
+
+    #[test]
+    fn built_in_levels_only_use_defined_segments() {
+        let levels = Levels::built_in();
+
+        assert!(levels.validate(&SegmentDefinitions::built_in()).is_ok());
+        assert_eq!(levels.levels.len(), 3);
+    }
+
+    #[test]
+    fn undefined_segments_and_empty_levels_fail_validation() {
+        let mut levels = Levels::built_in();
+        levels.levels[0].segments.push("nowhere".to_string());
+        levels.levels[1].segments.clear();
+
+        let message = levels
+            .validate(&SegmentDefinitions::built_in())
+            .unwrap_err()
+            .to_string();
+
+        assert!(message.contains("First Steps uses undefined segment nowhere"));
+        assert!(message.contains("Up and Over has no segments"));
+    }
+
+    #[test]
+    fn keeps_the_fastest_time_and_counts_completions() {
+        let mut records = LevelRecords::load(Box::<MemoryStorage>::default());
+
+        assert!(records.record("First Steps", 900));
+        assert!(!records.record("First Steps", 960));
+        assert!(records.record("First Steps", 750));
+
+        assert_eq!(records.best_frames("First Steps"), Some(750));
+        assert_eq!(records.completions("First Steps"), 3);
+        assert_eq!(records.best_time("First Steps"), "12.5s");
+        assert_eq!(records.best_time("Up and Over"), "-");
+    }
+
+    #[test]
+    fn records_survive_reloading_from_storage() {
+        let mut records = LevelRecords::load(Box::<MemoryStorage>::default());
+        records.record("Stone Garden", 1200);
+        let mut storage = MemoryStorage::default();
+        storage
+            .set(
+                STORAGE_KEY,
+                &serde_json::to_string(&records.results).unwrap(),
+            )
+            .unwrap();
+
+        let records = LevelRecords::load(Box::new(storage));
+
+        assert_eq!(records.best_frames("Stone Garden"), Some(1200));
+        assert_eq!(records.completions("Stone Garden"), 1);
+    }
+}
@@ -1,23 +1,35 @@
 pub mod barrier;
+pub mod finish_line;
 pub mod obstacle_mark;
 pub mod platform;
 
 use super::{dog::Dog, event_queue::EventSubscriber, red_hat_boy::RedHatBoy};
-use crate::engine::renderer::Renderer;
+use crate::engine::{broadphase::SweepIndex, renderer::Renderer};
 use obstacle_mark::ObstacleMark;
 use std::{cell::RefCell, rc::Rc};
 
 pub type ObstacleVec = Vec<Rc<RefCell<Box<dyn Obstacle>>>>;
+/// Obstacles by where they are along the course
+pub type ObstacleIndex = SweepIndex<Rc<RefCell<Box<dyn Obstacle>>>>;
 
 pub trait Obstacle: EventSubscriber {
-    fn check_intersection(&self, boy: &mut RedHatBoy);
+    /// Publish what happens to `boy` if he runs into the obstacle, which just
+    /// scrolled by `scrolled`. The boy reacts to it before the next obstacle
+    /// is checked.
+    fn check_intersection(&self, boy: &RedHatBoy, scrolled: i16);
     fn draw(&self, renderer: &Renderer);
     fn id(&self) -> String;
+    fn left(&self) -> i16;
     fn move_horizontally(&mut self, x: i16);
     fn navigate(&mut self, dog: &Dog);
     fn right(&self) -> i16;
 }
 
+/// State of an obstacle the dog can be on, for the event trace
+fn has_dog(has_dog: bool) -> String {
+    if has_dog { "has dog" } else { "empty" }.to_string()
+}
+
 pub trait ObstacleMarkFactory {
     // Dog navigation (jump point) mark left of the obstacle
     fn mark_left(&self) -> ObstacleMark;
@@ -3,35 +3,68 @@ use super::{
     Obstacle, ObstacleMarkFactory,
 };
 use crate::{
-    engine::{image::Image, rect::Point, renderer::Renderer},
+    engine::{
+        image::Image,
+        rect::{Point, Rect},
+        renderer::Renderer,
+    },
     game::{
         dog::Dog,
-        event_queue::{EventPublisher, EventSubscriber, GameEvent},
+        event_bus::Subscription,
+        event_queue::{EventPublisher, EventSubscriber, GameEvent, Phase},
         red_hat_boy::RedHatBoy,
     },
 };
+use std::cell::Cell;
+
+/// Horizontal gap between a barrier and its marks
+pub const MARK_GAP: i16 = 20;
+/// How far the right mark reaches above the barrier
+pub const RIGHT_MARK_RISE: i16 = 200;
 
 pub struct Barrier {
+    /// True once the boy got past without running into it
+    cleared: Cell<bool>,
     event_publisher: EventPublisher,
     id: String,
     image: Image,
+    /// Name of the segment the barrier was built for
+    segment: String,
 }
 
 impl Barrier {
-    pub fn new(id: String, image: Image, event_publisher: EventPublisher) -> Self {
+    pub fn new(id: String, segment: String, image: Image, event_publisher: EventPublisher) -> Self {
         Barrier {
-            event_publisher,
+            cleared: Cell::new(false),
+            event_publisher: event_publisher.named(&id),
             id,
             image,
+            segment,
         }
     }
 }
 
 impl Obstacle for Barrier {
-    fn check_intersection(&self, boy: &mut RedHatBoy) {
-        if boy.is_running() && boy.bounding_box().intersects(self.image.bounding_box()) {
-            boy.knock_out();
-            self.event_publisher.publish(GameEvent::BoyHitsObstacle);
+    fn check_intersection(&self, boy: &RedHatBoy, scrolled: i16) {
+        if boy.is_running() && boy.sweep(self.image.bounding_box(), scrolled).is_some() {
+            self.event_publisher.publish_in(
+                Phase::Immediate,
+                GameEvent::BoyHitsObstacle {
+                    id: self.id.clone(),
+                    segment: self.segment.clone(),
+                },
+            );
+        } else if !self.cleared.get()
+            && !boy.knocked_out()
+            && boy.bounding_box().left() > self.image.right()
+        {
+            self.cleared.set(true);
+            self.event_publisher.publish_in(
+                Phase::Immediate,
+                GameEvent::BoyClearedBarrier {
+                    id: self.id.clone(),
+                },
+            );
         }
     }
 
@@ -43,6 +76,10 @@ impl Obstacle for Barrier {
         self.id.to_string()
     }
 
+    fn left(&self) -> i16 {
+        self.image.bounding_box().left()
+    }
+
     fn move_horizontally(&mut self, x: i16) {
         self.image.move_horizontally(x);
     }
@@ -57,10 +94,7 @@ impl Obstacle for Barrier {
 impl ObstacleMarkFactory for Barrier {
     fn mark_left(&self) -> ObstacleMark {
         ObstacleMark::new(
-            Point {
-                x: self.image.bounding_box().x() - 20,
-                y: self.image.bounding_box().y(),
-            },
+            left_mark_position(self.image.bounding_box()),
             ObstacleMarkDirection::Left,
             self.id.clone(),
             self.event_publisher.clone(),
@@ -71,10 +105,7 @@ impl ObstacleMarkFactory for Barrier {
     // such as in the stone-and_platform segment
     fn mark_right(&self) -> ObstacleMark {
         ObstacleMark::new(
-            Point {
-                x: self.image.bounding_box().right() + 20,
-                y: self.image.bounding_box().y() - 200,
-            },
+            right_mark_position(self.image.bounding_box()),
             ObstacleMarkDirection::Right,
             self.id.clone(),
             self.event_publisher.clone(),
@@ -88,4 +119,24 @@ impl EventSubscriber for Barrier {
     }
 
     fn process_event(&mut self, _event: &GameEvent) {}
+
+    fn subscriptions(&self) -> Vec<Subscription> {
+        vec![]
+    }
+}
+
+/// Where the left mark goes for a barrier occupying `bounding_box`
+pub fn left_mark_position(bounding_box: &Rect) -> Point {
+    Point {
+        x: bounding_box.x() - MARK_GAP,
+        y: bounding_box.y(),
+    }
+}
+
+/// Where the right mark goes for a barrier occupying `bounding_box`
+pub fn right_mark_position(bounding_box: &Rect) -> Point {
+    Point {
+        x: bounding_box.right() + MARK_GAP,
+        y: bounding_box.y() - RIGHT_MARK_RISE,
+    }
 }
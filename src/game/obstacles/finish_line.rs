@@ -0,0 +1,98 @@
+use super::Obstacle;
+use crate::{
+    engine::{rect::Rect, renderer::Renderer},
+    game::{
+        dog::Dog,
+        event_bus::Subscription,
+        event_queue::{EventPublisher, EventSubscriber, GameEvent},
+        red_hat_boy::RedHatBoy,
+        HEIGHT,
+    },
+};
+use std::cell::Cell;
+
+/// Checkered squares across and down the flag
+const COLUMNS: i16 = 2;
+const ROWS: i16 = 16;
+const SQUARE: i16 = 12;
+
+/// The end of a level. Publishes `GameEvent::LevelFinished` once the boy is
+/// past it.
+pub struct FinishLine {
+    crossed: Cell<bool>,
+    event_publisher: EventPublisher,
+    id: String,
+    x: i16,
+}
+
+impl FinishLine {
+    pub fn new(id: String, x: i16, event_publisher: EventPublisher) -> Self {
+        FinishLine {
+            crossed: Cell::new(false),
+            event_publisher: event_publisher.named(&id),
+            id,
+            x,
+        }
+    }
+}
+
+impl Obstacle for FinishLine {
+    fn check_intersection(&self, boy: &RedHatBoy, _scrolled: i16) {
+        if !self.crossed.get() && !boy.knocked_out() && boy.bounding_box().left() > self.x {
+            self.crossed.set(true);
+            self.event_publisher.publish(GameEvent::LevelFinished);
+        }
+    }
+
+    fn draw(&self, renderer: &Renderer) {
+        let top = HEIGHT - ROWS * SQUARE;
+        for row in 0..ROWS {
+            for column in 0..COLUMNS {
+                let color = if (row + column) % 2 == 0 {
+                    "#000000"
+                } else {
+                    "#FFFFFF"
+                };
+                renderer.fill_rect(
+                    &Rect::new_from_x_y(
+                        self.x + column * SQUARE,
+                        top + row * SQUARE,
+                        SQUARE,
+                        SQUARE,
+                    ),
+                    color,
+                );
+            }
+        }
+    }
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn left(&self) -> i16 {
+        self.x
+    }
+
+    fn move_horizontally(&mut self, x: i16) {
+        self.x += x;
+    }
+
+    fn navigate(&mut self, _dog: &Dog) {}
+
+    fn right(&self) -> i16 {
+        self.x + COLUMNS * SQUARE
+    }
+}
+
+impl EventSubscriber for FinishLine {
+    fn name(&self) -> String {
+        self.id()
+    }
+
+    fn process_event(&mut self, _event: &GameEvent) {}
+
+    fn subscriptions(&self) -> Vec<Subscription> {
+        vec![]
+    }
+}
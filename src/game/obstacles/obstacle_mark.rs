@@ -2,7 +2,8 @@ use super::Obstacle;
 use crate::{
     engine::rect::{Point, Rect},
     game::{
-        event_queue::{EventPublisher, EventSubscriber, GameEvent},
+        event_bus::{self, Subscription},
+        event_queue::{EventPublisher, EventSubscriber, GameEvent, GameEventKind, Phase},
         red_hat_boy::RedHatBoy,
         HEIGHT,
     },
@@ -35,22 +36,29 @@ impl ObstacleMark {
             ObstacleMarkDirection::Right => "r",
         };
 
+        let id = format!("{}_{dir}m", obstacle_id);
+
         ObstacleMark {
-            id: format!("{}_{dir}m", obstacle_id),
+            event_publisher: event_publisher.named(&id),
+            id,
             direction,
-            event_publisher,
             has_dog: false,
             position,
         }
     }
 
     fn mark(&self) -> Rect {
-        Rect::new(self.position, 1, HEIGHT - self.position.y)
+        mark_rect(self.position)
     }
 }
 
+/// Area of a mark at `position`: a 1 pixel wide line down to the ground
+pub fn mark_rect(position: Point) -> Rect {
+    Rect::new(position, 1, HEIGHT - position.y)
+}
+
 impl Obstacle for ObstacleMark {
-    fn check_intersection(&self, _boy: &mut RedHatBoy) {}
+    fn check_intersection(&self, _boy: &RedHatBoy, _scrolled: i16) {}
 
     fn draw(&self, renderer: &crate::engine::renderer::Renderer) {
         let color = match self.direction {
@@ -65,6 +73,10 @@ impl Obstacle for ObstacleMark {
         self.id.to_string()
     }
 
+    fn left(&self) -> i16 {
+        self.position.x
+    }
+
     fn move_horizontally(&mut self, x: i16) {
         self.position.x += x;
     }
@@ -76,16 +88,23 @@ impl Obstacle for ObstacleMark {
 
         let is_on_mark = is_relevant && dog.bounding_box().intersects(&self.mark());
 
+        // Immediate, so the dog jumps before it next moves
         if is_on_mark && !self.has_dog {
-            self.event_publisher.publish(GameEvent::DogHitMark {
-                id: self.id.clone(),
-            });
+            self.event_publisher.publish_in(
+                Phase::Immediate,
+                GameEvent::DogHitMark {
+                    id: self.id.clone(),
+                },
+            );
         }
 
         if !is_on_mark && self.has_dog {
-            self.event_publisher.publish(GameEvent::DogOffMark {
-                id: self.id.clone(),
-            });
+            self.event_publisher.publish_in(
+                Phase::Immediate,
+                GameEvent::DogOffMark {
+                    id: self.id.clone(),
+                },
+            );
         }
     }
 
@@ -99,17 +118,52 @@ impl EventSubscriber for ObstacleMark {
         self.id()
     }
 
-    fn process_event(&mut self, event: &crate::game::event_queue::GameEvent) {
-        match event {
-            GameEvent::DogHitMark { id } if *id == self.id => {
-                log!("Mark {}: Dog hit mark", self.id);
-                self.has_dog = true;
-            }
-            GameEvent::DogOffMark { id } if *id == self.id => {
-                log!("Mark {}: Dog off mark", self.id);
-                self.has_dog = false;
-            }
-            _ => (),
+    fn process_event(&mut self, event: &GameEvent) {
+        if let Some(has_dog) = has_dog_after(event) {
+            log!("Mark {}: {event:?}", self.id);
+            self.has_dog = has_dog;
         }
     }
+
+    fn state(&self) -> Option<String> {
+        Some(super::has_dog(self.has_dog))
+    }
+
+    /// Marks know whether the dog is on them before anything reacts to it
+    fn subscriptions(&self) -> Vec<Subscription> {
+        REACTS_TO
+            .into_iter()
+            .map(|kind| {
+                Subscription::to(kind)
+                    .targeting(&self.id)
+                    .with_priority(event_bus::FIRST)
+            })
+            .collect()
+    }
+}
+
+/// Kinds of `GameEvent` that `has_dog_after` knows about
+const REACTS_TO: [GameEventKind; 2] = [GameEventKind::DogHitMark, GameEventKind::DogOffMark];
+
+/// Whether the dog is on the mark after `event`, if it says
+fn has_dog_after(event: &GameEvent) -> Option<bool> {
+    match event {
+        GameEvent::DogHitMark { .. } => Some(true),
+        GameEvent::DogOffMark { .. } => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::event_queue::handled_kinds;
+
+    #[test]
+    fn subscribes_to_exactly_the_events_it_reacts_to() {
+        assert_eq!(
+            handled_kinds(|event| has_dog_after(event).is_some()),
+            REACTS_TO.into_iter().collect()
+        );
+    }
 }
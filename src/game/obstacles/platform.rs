@@ -11,14 +11,15 @@ use crate::{
     },
     game::{
         dog::Dog,
-        event_queue::{EventPublisher, EventSubscriber, GameEvent},
+        event_bus::{self, Subscription},
+        event_queue::{EventPublisher, EventSubscriber, GameEvent, GameEventKind, Phase},
         red_hat_boy::RedHatBoy,
     },
 };
 use std::rc::Rc;
 
-const LEFT_MARK_OFFSET: i16 = 80;
-const RIGHT_MARK_OFFSET: i16 = 100;
+pub const LEFT_MARK_OFFSET: i16 = 80;
+pub const RIGHT_MARK_OFFSET: i16 = 100;
 
 #[derive(Debug)]
 pub struct Platform {
@@ -28,6 +29,8 @@ pub struct Platform {
     /// True when dog is running on platform
     has_dog: bool,
     id: String,
+    /// Name of the segment the platform was built for
+    segment: String,
     sheet: Rc<SpriteSheet>,
     sprites: Vec<Cell>,
 }
@@ -35,6 +38,7 @@ pub struct Platform {
 impl Platform {
     pub fn new(
         id: String,
+        segment: String,
         sheet: Rc<SpriteSheet>,
         position: Point,
         sprite_names: &[&str],
@@ -52,10 +56,11 @@ impl Platform {
 
         Platform {
             bounding_boxes,
-            event_publisher,
+            event_publisher: event_publisher.named(&id),
             id,
             has_dog: false,
             position,
+            segment,
             sheet,
             sprites,
         }
@@ -88,22 +93,31 @@ impl Platform {
 }
 
 impl Obstacle for Platform {
-    fn check_intersection(&self, boy: &mut RedHatBoy) {
+    /// The boy lands if the first box he touches is touched on top, or he
+    /// was already standing on it
+    fn check_intersection(&self, boy: &RedHatBoy, scrolled: i16) {
         if !boy.is_running() {
             return;
         }
 
-        if let Some(box_to_land_on) = self
+        if let Some((box_to_land_on, hit)) = self
             .bounding_boxes()
             .iter()
-            .find(|&b| boy.bounding_box().intersects(b))
+            .filter_map(|b| boy.sweep(b, scrolled).map(|hit| (b, hit)))
+            .min_by(|(_, first), (_, second)| first.time.total_cmp(&second.time))
         {
-            if boy.velocity_y() > 0 && boy.position_y() < self.position.y {
-                boy.land_on(box_to_land_on.top());
+            let event = if hit.on_top() || (hit.time == 0.0 && boy.on_platform()) {
+                GameEvent::BoyOnPlatform {
+                    id: self.id.clone(),
+                    platform_top: box_to_land_on.top(),
+                }
             } else {
-                boy.knock_out();
-                self.event_publisher.publish(GameEvent::BoyHitsObstacle);
-            }
+                GameEvent::BoyHitsObstacle {
+                    id: self.id.clone(),
+                    segment: self.segment.clone(),
+                }
+            };
+            self.event_publisher.publish_in(Phase::Immediate, event);
         }
     }
 
@@ -139,6 +153,13 @@ impl Obstacle for Platform {
         self.id.to_string()
     }
 
+    fn left(&self) -> i16 {
+        self.bounding_boxes()
+            .first()
+            .unwrap_or(&Rect::default())
+            .left()
+    }
+
     fn move_horizontally(&mut self, x: i16) {
         self.position.x += x;
         self.bounding_boxes
@@ -154,15 +175,18 @@ impl Obstacle for Platform {
         if is_on_platform && !self.has_dog {
             assert!(!dog.moving_up());
 
-            self.event_publisher
-                .publish(GameEvent::DogLandedOnPlatform {
+            self.event_publisher.publish_in(
+                Phase::Immediate,
+                GameEvent::DogLandedOnPlatform {
                     id: self.id.clone(),
                     platform_top: self.position.y,
-                });
+                },
+            );
         }
 
         if !is_on_platform && self.has_dog {
-            self.event_publisher.publish(GameEvent::DogExitsPlatform);
+            self.event_publisher
+                .publish_in(Phase::Immediate, GameEvent::DogExitsPlatform);
         }
     }
 
@@ -177,10 +201,7 @@ impl Obstacle for Platform {
 impl ObstacleMarkFactory for Platform {
     fn mark_left(&self) -> ObstacleMark {
         ObstacleMark::new(
-            Point {
-                x: self.position.x - LEFT_MARK_OFFSET,
-                y: self.position.y,
-            },
+            left_mark_position(self.position),
             ObstacleMarkDirection::Left,
             self.id.clone(),
             self.event_publisher.clone(),
@@ -189,10 +210,7 @@ impl ObstacleMarkFactory for Platform {
 
     fn mark_right(&self) -> ObstacleMark {
         ObstacleMark::new(
-            Point {
-                x: self.right() + RIGHT_MARK_OFFSET,
-                y: self.position.y,
-            },
+            right_mark_position(self.position, self.right()),
             ObstacleMarkDirection::Right,
             self.id.clone(),
             self.event_publisher.clone(),
@@ -212,16 +230,74 @@ impl EventSubscriber for Platform {
     }
 
     fn process_event(&mut self, event: &GameEvent) {
-        match event {
-            GameEvent::DogExitsPlatform if self.has_dog => {
-                log!("Platform {}: Dog exited platform", self.id);
-                self.has_dog = false;
-            }
-            GameEvent::DogLandedOnPlatform { id, .. } if *id == self.id && !self.has_dog => {
-                log!("Platform {}: Dog landed on platform", self.id);
-                self.has_dog = true
-            }
-            _ => (),
+        if let Some(has_dog) = has_dog_after(event).filter(|has_dog| *has_dog != self.has_dog) {
+            log!("Platform {}: {event:?}", self.id);
+            self.has_dog = has_dog;
         }
     }
+
+    fn state(&self) -> Option<String> {
+        Some(super::has_dog(self.has_dog))
+    }
+
+    /// Platforms know where the dog is before anything reacts to it. The dog
+    /// lands on one platform, but leaves whichever it is on.
+    fn subscriptions(&self) -> Vec<Subscription> {
+        REACTS_TO
+            .into_iter()
+            .map(|kind| {
+                let subscription = Subscription::to(kind).with_priority(event_bus::FIRST);
+                match kind {
+                    GameEventKind::DogLandedOnPlatform => subscription.targeting(&self.id),
+                    _ => subscription,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Where the left mark goes for a platform at `position`
+pub fn left_mark_position(position: Point) -> Point {
+    Point {
+        x: position.x - LEFT_MARK_OFFSET,
+        y: position.y,
+    }
+}
+
+/// Where the right mark goes for a platform at `position` whose last
+/// bounding box ends at `right`
+pub fn right_mark_position(position: Point, right: i16) -> Point {
+    Point {
+        x: right + RIGHT_MARK_OFFSET,
+        y: position.y,
+    }
+}
+
+/// Kinds of `GameEvent` that `has_dog_after` knows about
+const REACTS_TO: [GameEventKind; 2] = [
+    GameEventKind::DogExitsPlatform,
+    GameEventKind::DogLandedOnPlatform,
+];
+
+/// Whether the dog is on the platform after `event`, if it says
+fn has_dog_after(event: &GameEvent) -> Option<bool> {
+    match event {
+        GameEvent::DogExitsPlatform => Some(false),
+        GameEvent::DogLandedOnPlatform { .. } => Some(true),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::event_queue::handled_kinds;
+
+    #[test]
+    fn subscribes_to_exactly_the_events_it_reacts_to() {
+        assert_eq!(
+            handled_kinds(|event| has_dog_after(event).is_some()),
+            REACTS_TO.into_iter().collect()
+        );
+    }
 }
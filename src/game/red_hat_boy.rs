@@ -1,54 +1,60 @@
 pub mod context;
 mod state_machine;
-mod states;
+pub mod states;
 
+use super::{
+    event_bus::{self, Subscription},
+    event_queue::{EventPublisher, EventSubscriber, GameEvent, GameEventKind, Phase},
+};
 use crate::engine::{
-    audio::Audio,
-    rect::Rect,
+    physics::{self, Contact, ContactChange, Sweep},
+    rect::{Point, Rect},
     renderer::Renderer,
     sheet::{Cell, Sheet},
 };
-use context::Sfx;
 use state_machine::{Event, RedHatBoyStateMachine};
-use states::RedHatBoyState;
+use states::{RedHatBoyState, ANIMATIONS};
 use web_sys::HtmlImageElement;
 
 pub struct RedHatBoy {
+    event_publisher: EventPublisher,
     image: HtmlImageElement,
     sprite_sheet: Sheet,
     state_machine: RedHatBoyStateMachine,
 }
 
 impl RedHatBoy {
-    pub fn new(audio: Audio, sfx: Sfx, sprite_sheet: Sheet, image: HtmlImageElement) -> Self {
+    pub fn new(
+        sprite_sheet: Sheet,
+        image: HtmlImageElement,
+        event_publisher: EventPublisher,
+    ) -> Self {
         RedHatBoy {
+            event_publisher: event_publisher.named("RedHatBoy"),
             image,
             sprite_sheet,
-            state_machine: RedHatBoyStateMachine::Idle(RedHatBoyState::new(audio, sfx)),
+            state_machine: RedHatBoyStateMachine::Idle(RedHatBoyState::new()),
         }
     }
 
-    pub fn reset(boy: Self) -> Self {
-        RedHatBoy::new(
-            boy.state_machine.context().audio.clone(),
-            boy.state_machine.context().sfx.clone(),
-            boy.sprite_sheet,
-            boy.image,
-        )
+    /// Back to standing at the start, in place so whoever holds the boy
+    /// keeps holding the same one
+    pub fn reset(&mut self) {
+        self.state_machine = RedHatBoyStateMachine::Idle(RedHatBoyState::new());
     }
 
-    pub fn bounding_box(&self) -> Rect {
-        const X_OFFSET: i16 = 18;
-        const Y_OFFSET: i16 = 14;
-        const WIDTH_OFFSET: i16 = 28;
-
-        let mut bounding_box = self.destination_box();
-        bounding_box.set_x(bounding_box.x() + X_OFFSET);
-        bounding_box.width -= WIDTH_OFFSET;
-        bounding_box.position.y += Y_OFFSET;
-        bounding_box.height -= Y_OFFSET;
+    /// Every sprite sheet frame name the state machine can request
+    pub fn required_frames() -> Vec<String> {
+        ANIMATIONS
+            .iter()
+            .flat_map(|(animation, frames)| {
+                (0..=*frames).map(move |frame| sprite_frame_name(animation, frame))
+            })
+            .collect()
+    }
 
-        bounding_box
+    pub fn bounding_box(&self) -> Rect {
+        collision_box(self.destination_box())
     }
 
     pub fn draw(&self, renderer: &Renderer) {
@@ -74,43 +80,63 @@ impl RedHatBoy {
     }
 
     pub fn jump(&mut self) {
-        self.state_machine = self.state_machine.clone().transition(Event::Jump);
-    }
-
-    pub fn knock_out(&mut self) {
-        self.state_machine = self.state_machine.clone().transition(Event::KnockOut);
+        self.transition(Event::Jump);
     }
 
     pub fn knocked_out(&self) -> bool {
         self.state_machine.knocked_out()
     }
 
-    pub fn land_on(&mut self, position: i16) {
-        self.state_machine = self.state_machine.clone().transition(Event::Land(position));
-    }
-
-    pub fn position_y(&self) -> i16 {
-        self.state_machine.context().position.y
+    /// Whether a platform is holding the boy up
+    pub fn on_platform(&self) -> bool {
+        self.state_machine.context().body.contact() == Contact::Platform
     }
 
     pub fn run_right(&mut self) {
-        self.state_machine = self.state_machine.clone().transition(Event::Run);
+        self.transition(Event::Run);
     }
 
     pub fn slide(&mut self) {
-        self.state_machine = self.state_machine.clone().transition(Event::Slide);
+        self.transition(Event::Slide);
     }
 
+    /// Publishes `GameEvent::BoyFellOffPlatform` if the boy was on a platform
+    /// but no platform held him up since the last update
     pub fn update(&mut self) {
-        self.state_machine = self.state_machine.clone().update();
+        let on_foot = matches!(
+            self.state_machine,
+            RedHatBoyStateMachine::Running(_) | RedHatBoyStateMachine::Sliding(_)
+        );
+        self.transition(Event::Update);
+
+        let left_platform = self.state_machine.context().contact_change
+            == Some(ContactChange::Left(Contact::Platform));
+        if on_foot && left_platform {
+            self.publish(GameEvent::BoyFellOffPlatform);
+        }
     }
 
-    pub fn velocity_y(&self) -> i16 {
-        self.state_machine.context().velocity.y
+    /// Where the boy first touched `obstacle` this frame, moving relative to
+    /// it while it scrolled by `scrolled`
+    pub fn sweep(&self, obstacle: &Rect, scrolled: i16) -> Option<Sweep> {
+        let moved = self.state_machine.context().body.displacement();
+        let motion = Point {
+            x: moved.x - scrolled,
+            y: moved.y,
+        };
+        let bounding_box = self.bounding_box();
+        let from = Rect::new_from_x_y(
+            bounding_box.x() - motion.x,
+            bounding_box.y() - motion.y,
+            bounding_box.width,
+            bounding_box.height,
+        );
+
+        physics::sweep(&from, motion, obstacle)
     }
 
     pub fn walking_speed(&self) -> i16 {
-        self.state_machine.context().velocity.x
+        self.state_machine.context().body.velocity.x
     }
 
     fn current_sprite(&self) -> &Cell {
@@ -124,18 +150,195 @@ impl RedHatBoy {
     fn destination_box(&self) -> Rect {
         let sprite = self.current_sprite();
         Rect::new_from_x_y(
-            self.state_machine.context().position.x + sprite.sprite_source_size.x,
-            self.state_machine.context().position.y + sprite.sprite_source_size.y,
+            self.state_machine.context().body.position.x + sprite.sprite_source_size.x,
+            self.state_machine.context().body.position.y + sprite.sprite_source_size.y,
             sprite.frame.w,
             sprite.frame.h,
         )
     }
 
     fn frame_name(&self) -> String {
-        format!(
-            "{} ({}).png",
+        sprite_frame_name(
             self.state_machine.frame_name(),
-            (self.state_machine.context().frame / 3) + 1
+            self.state_machine.context().frame,
         )
     }
+
+    /// Publish what the boy did, for subscribers to handle straight away
+    fn publish(&self, event: GameEvent) {
+        self.event_publisher.publish_in(Phase::Immediate, event);
+    }
+
+    /// Move the state machine on with `event`. Returns the event for what
+    /// the boy did, if anything.
+    fn advance(&mut self, event: Event) -> Option<GameEvent> {
+        let next = self.state_machine.clone().transition(event);
+        let published = published_event(&self.state_machine, &next);
+        self.state_machine = next;
+
+        published
+    }
+
+    /// Move the state machine on with `event`, publishing what the boy did
+    fn transition(&mut self, event: Event) {
+        if let Some(event) = self.advance(event) {
+            self.publish(event);
+        }
+    }
+}
+
+impl EventSubscriber for RedHatBoy {
+    fn name(&self) -> String {
+        "RedHatBoy".to_string()
+    }
+
+    /// Landing on a platform is published as landing on that platform
+    fn process_event(&mut self, event: &GameEvent) {
+        let Some(reaction) = reaction_to(event) else {
+            return;
+        };
+        let published = self
+            .advance(reaction)
+            .map(|published| match (published, event.target()) {
+                (GameEvent::BoyLanded, Some(id)) => {
+                    GameEvent::BoyLandedOnPlatform { id: id.to_string() }
+                }
+                (published, _) => published,
+            });
+        if let Some(published) = published {
+            self.publish(published);
+        }
+    }
+
+    fn state(&self) -> Option<String> {
+        Some(self.state_machine.state_name().to_string())
+    }
+
+    /// Obstacles only report what the boy ran into. He has to react before
+    /// the next obstacle looks at him, so these come before anyone else.
+    fn subscriptions(&self) -> Vec<Subscription> {
+        REACTS_TO
+            .into_iter()
+            .map(|kind| Subscription::to(kind).with_priority(event_bus::FIRST))
+            .collect()
+    }
+}
+
+/// Kinds of `GameEvent` that `reaction_to` has the boy react to
+const REACTS_TO: [GameEventKind; 2] =
+    [GameEventKind::BoyHitsObstacle, GameEventKind::BoyOnPlatform];
+
+/// How the boy reacts to `event`, if at all
+fn reaction_to(event: &GameEvent) -> Option<Event> {
+    match event {
+        GameEvent::BoyHitsObstacle { .. } => Some(Event::KnockOut),
+        GameEvent::BoyOnPlatform { platform_top, .. } => Some(Event::Land(*platform_top)),
+        _ => None,
+    }
+}
+
+/// The event for the boy moving from state `before` to `after`, if any
+fn published_event(
+    before: &RedHatBoyStateMachine,
+    after: &RedHatBoyStateMachine,
+) -> Option<GameEvent> {
+    match (before, after) {
+        (RedHatBoyStateMachine::Running(_), RedHatBoyStateMachine::Jumping(_)) => {
+            Some(GameEvent::BoyJumped)
+        }
+        (RedHatBoyStateMachine::Jumping(_), RedHatBoyStateMachine::Running(_)) => {
+            Some(GameEvent::BoyLanded)
+        }
+        (RedHatBoyStateMachine::Running(_), RedHatBoyStateMachine::Sliding(_)) => {
+            Some(GameEvent::BoySlid)
+        }
+        (RedHatBoyStateMachine::Falling(_), _) => None,
+        (_, RedHatBoyStateMachine::Falling(_)) => Some(GameEvent::BoyKnockedOut),
+        _ => None,
+    }
+}
+
+/// The part of a sprite drawn at `destination_box` that obstacles collide with
+pub fn collision_box(destination_box: Rect) -> Rect {
+    const X_OFFSET: i16 = 18;
+    const Y_OFFSET: i16 = 14;
+    const WIDTH_OFFSET: i16 = 28;
+
+    let mut bounding_box = destination_box;
+    bounding_box.set_x(bounding_box.x() + X_OFFSET);
+    bounding_box.width -= WIDTH_OFFSET;
+    bounding_box.position.y += Y_OFFSET;
+    bounding_box.height -= Y_OFFSET;
+
+    bounding_box
+}
+
+pub fn sprite_frame_name(animation: &str, frame: u8) -> String {
+    format!("{} ({}).png", animation, (frame / 3) + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::event_queue::{self, Events};
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[test]
+    fn subscribes_to_exactly_the_events_it_reacts_to() {
+        assert_eq!(
+            event_queue::handled_kinds(|event| reaction_to(event).is_some()),
+            REACTS_TO.into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn required_frames_cover_each_animation_up_to_its_last_frame() {
+        let frames = RedHatBoy::required_frames();
+
+        assert!(frames.contains(&"Run (8).png".to_string()));
+        assert!(!frames.contains(&"Run (9).png".to_string()));
+        assert!(frames.contains(&"Jump (12).png".to_string()));
+        assert!(frames.contains(&"Slide (5).png".to_string()));
+    }
+
+    #[wasm_bindgen_test]
+    fn publishes_what_the_boy_does_and_reacts_to_obstacles() {
+        let events = Rc::new(RefCell::new(Events::default()));
+        let mut boy = RedHatBoy::new(
+            Sheet {
+                frames: HashMap::new(),
+            },
+            HtmlImageElement::new().unwrap(),
+            EventPublisher::new(events.clone()),
+        );
+
+        boy.run_right();
+        boy.jump();
+        boy.process_event(&GameEvent::BoyOnPlatform {
+            id: "p1".to_string(),
+            platform_top: 400,
+        });
+        boy.update();
+        boy.update();
+        boy.process_event(&GameEvent::BoyHitsObstacle {
+            id: "b1".to_string(),
+            segment: "stone".to_string(),
+        });
+
+        let mut published = Vec::new();
+        event_queue::dispatch_phase(&events, Phase::Immediate, |published_event| {
+            published.push(published_event.event.kind())
+        });
+        assert_eq!(
+            published,
+            vec![
+                GameEventKind::BoyJumped,
+                GameEventKind::BoyLandedOnPlatform,
+                GameEventKind::BoyFellOffPlatform,
+                GameEventKind::BoyKnockedOut
+            ]
+        );
+        assert!(!boy.is_running());
+    }
 }
@@ -1,56 +1,37 @@
 use crate::{
     engine::{
-        audio::{Audio, Sound},
+        physics::{ContactChange, KinematicBody},
         rect::Point,
     },
     game,
 };
 
 pub const FLOOR: i16 = game::HEIGHT - PLAYER_HEIGHT;
-const PLAYER_HEIGHT: i16 = 121;
+pub const PLAYER_HEIGHT: i16 = 121;
 
 #[derive(Clone)]
 pub struct RedHatBoyContext {
-    pub audio: Audio,
+    pub body: KinematicBody,
+    /// How the last update changed what the boy is standing on
+    pub contact_change: Option<ContactChange>,
     pub frame: u8,
-    pub position: Point,
-    pub sfx: Sfx,
-    pub velocity: Point,
 }
 
 impl RedHatBoyContext {
-    pub fn new(audio: Audio, frame: u8, position: Point, sfx: Sfx, velocity: Point) -> Self {
-        RedHatBoyContext {
-            audio,
-            frame,
+    pub fn new(frame: u8, position: Point, velocity: Point) -> Self {
+        let mut body = KinematicBody::new(
             position,
-            sfx,
-            velocity,
-        }
-    }
-
-    pub fn play_jump_sfx(self) -> Self {
-        if let Err(err) = self.audio.play_sound(&self.sfx.jump) {
-            log!("Error playing jump sound {:#?}", err);
-        }
-
-        self
-    }
-
-    pub fn play_ko_sfx(self) -> Self {
-        if let Err(err) = self.audio.play_sound(&self.sfx.ko) {
-            log!("Error playing knock-out sound {:#?}", err);
-        }
-
-        self
-    }
+            PLAYER_HEIGHT,
+            game::HEIGHT,
+            game::TERMINAL_VELOCITY,
+        );
+        body.velocity = velocity;
 
-    pub fn play_slide_sfx(self) -> Self {
-        if let Err(err) = self.audio.play_sound(&self.sfx.slide) {
-            log!("Error playing slide sound {:#?}", err);
+        RedHatBoyContext {
+            body,
+            contact_change: None,
+            frame,
         }
-
-        self
     }
 
     pub fn reset_frame(mut self) -> Self {
@@ -60,27 +41,26 @@ impl RedHatBoyContext {
     }
 
     pub fn run_right(mut self) -> Self {
-        self.velocity.x += game::RUNNING_SPEED;
+        self.body.velocity.x += game::RUNNING_SPEED;
 
         self
     }
 
+    /// Stand on a platform whose top is at `position`
     pub fn set_on(mut self, position: i16) -> Self {
-        let position = position - PLAYER_HEIGHT;
-        self.position.y = position;
+        self.body.rest_on(position);
 
         self
     }
 
     pub fn set_vertical_velocity(mut self, y: i16) -> Self {
-        self.velocity.y = y;
+        self.body.velocity.y = y;
 
         self
     }
 
     pub fn stop(mut self) -> Self {
-        self.velocity.x = 0;
-        self.velocity.y = 0;
+        self.body.velocity = Point::default();
 
         self
     }
@@ -88,35 +68,14 @@ impl RedHatBoyContext {
     /// Update the frame count or loop back to frame 0 when current frame hits
     ///  `frame_count` (the number of frames in the active state animation)
     pub fn update(mut self, frame_count: u8) -> Self {
-        if self.velocity.y < game::TERMINAL_VELOCITY {
-            self.velocity.y += game::GRAVITY;
-        }
-
         if self.frame < frame_count {
             self.frame += 1;
         } else {
             self.frame = 0;
         }
 
-        self.position.y += self.velocity.y;
-
-        if self.position.y > FLOOR {
-            self.position.y = FLOOR;
-        }
+        self.contact_change = self.body.step();
 
         self
     }
 }
-
-#[derive(Clone)]
-pub struct Sfx {
-    jump: Sound,
-    ko: Sound,
-    slide: Sound,
-}
-
-impl Sfx {
-    pub fn new(jump: Sound, ko: Sound, slide: Sound) -> Self {
-        Sfx { jump, ko, slide }
-    }
-}
@@ -50,6 +50,17 @@ impl RedHatBoyStateMachine {
         }
     }
 
+    pub fn state_name(&self) -> &'static str {
+        match self {
+            RedHatBoyStateMachine::Falling(_) => "Falling",
+            RedHatBoyStateMachine::Idle(_) => "Idle",
+            RedHatBoyStateMachine::Jumping(_) => "Jumping",
+            RedHatBoyStateMachine::KnockedOut(_) => "KnockedOut",
+            RedHatBoyStateMachine::Running(_) => "Running",
+            RedHatBoyStateMachine::Sliding(_) => "Sliding",
+        }
+    }
+
     pub fn knocked_out(&self) -> bool {
         matches!(self, RedHatBoyStateMachine::KnockedOut(_))
     }
@@ -89,10 +100,6 @@ impl RedHatBoyStateMachine {
             _ => self,
         }
     }
-
-    pub fn update(self) -> Self {
-        self.transition(Event::Update)
-    }
 }
 
 impl From<RedHatBoyState<Idle>> for RedHatBoyStateMachine {
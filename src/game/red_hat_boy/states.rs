@@ -7,6 +7,17 @@ pub mod sliding;
 
 use super::context::RedHatBoyContext;
 
+/// (animation name, last frame count) for every animation the state machine
+/// can display. `KnockedOut` holds the last frame of the `Falling` animation.
+pub const ANIMATIONS: [(&str, u8); 6] = [
+    (falling::FALL_FRAME_NAME, falling::FALLING_FRAMES),
+    (idle::IDLE_FRAME_NAME, idle::IDLE_FRAMES),
+    (jumping::JUMP_FRAME_NAME, jumping::JUMPING_FRAMES),
+    (knocked_out::DEAD_FRAME_NAME, falling::FALLING_FRAMES),
+    (running::RUN_FRAME_NAME, running::RUNNING_FRAMES),
+    (sliding::SLIDE_FRAME_NAME, sliding::SLIDING_FRAMES),
+];
+
 #[derive(Clone)]
 pub struct RedHatBoyState<S> {
     context: RedHatBoyContext,
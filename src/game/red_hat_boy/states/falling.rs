@@ -1,8 +1,8 @@
 use super::{knocked_out::KnockedOut, RedHatBoyState};
 use crate::game::red_hat_boy::state_machine::RedHatBoyStateMachine;
 
-const FALL_FRAME_NAME: &str = "Dead";
-const FALLING_FRAMES: u8 = 29;
+pub const FALL_FRAME_NAME: &str = "Dead";
+pub const FALLING_FRAMES: u8 = 29;
 
 #[derive(Clone, Copy)]
 pub struct Falling;
@@ -26,7 +26,7 @@ impl RedHatBoyState<Falling> {
         log!("Falling->KnockedOut");
 
         RedHatBoyState {
-            context: self.context.play_ko_sfx(),
+            context: self.context,
             _state: KnockedOut {},
         }
     }
@@ -1,28 +1,26 @@
 use super::{running::Running, RedHatBoyState};
 use crate::{
-    engine::{audio::Audio, rect::Point},
-    game::red_hat_boy::context::{self, RedHatBoyContext, FLOOR},
+    engine::rect::Point,
+    game::red_hat_boy::context::{RedHatBoyContext, FLOOR},
 };
 
-const STARTING_POINT: i16 = -60;
+pub const STARTING_POINT: i16 = -60;
 
 #[derive(Clone, Copy)]
 pub struct Idle;
 
-const IDLE_FRAME_NAME: &str = "Idle";
-const IDLE_FRAMES: u8 = 29;
+pub const IDLE_FRAME_NAME: &str = "Idle";
+pub const IDLE_FRAMES: u8 = 29;
 
 impl RedHatBoyState<Idle> {
-    pub fn new(audio: Audio, sfx: context::Sfx) -> Self {
+    pub fn new() -> Self {
         RedHatBoyState {
             context: RedHatBoyContext::new(
-                audio,
                 0,
                 Point {
                     x: STARTING_POINT,
                     y: FLOOR,
                 },
-                sfx,
                 Point { x: 0, y: 0 },
             ),
             _state: Idle,
@@ -1,11 +1,11 @@
 use super::{falling::Falling, running::Running, RedHatBoyState};
-use crate::game::{
-    self,
-    red_hat_boy::{context::FLOOR, state_machine::RedHatBoyStateMachine},
+use crate::{
+    engine::physics::Contact,
+    game::{self, red_hat_boy::state_machine::RedHatBoyStateMachine},
 };
 
-const JUMP_FRAME_NAME: &str = "Jump";
-const JUMPING_FRAMES: u8 = 35;
+pub const JUMP_FRAME_NAME: &str = "Jump";
+pub const JUMPING_FRAMES: u8 = 35;
 
 #[derive(Clone, Copy)]
 pub struct Jumping;
@@ -36,7 +36,7 @@ impl RedHatBoyState<Jumping> {
     pub fn update(mut self) -> JumpingEndState {
         self.context = self.context.update(JUMPING_FRAMES);
 
-        if self.context.position.y >= FLOOR {
+        if self.context.body.contact() == Contact::Ground {
             JumpingEndState::Landing(self.land_on(game::HEIGHT))
         } else {
             JumpingEndState::Jumping(self)
@@ -1,6 +1,6 @@
 use super::RedHatBoyState;
 
-const DEAD_FRAME_NAME: &str = "Dead";
+pub const DEAD_FRAME_NAME: &str = "Dead";
 
 #[derive(Clone, Copy)]
 pub struct KnockedOut;
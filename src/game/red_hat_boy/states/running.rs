@@ -1,12 +1,12 @@
 use super::{falling::Falling, jumping::Jumping, sliding::Sliding, RedHatBoyState};
 
-const RUN_FRAME_NAME: &str = "Run";
-const RUNNING_FRAMES: u8 = 23;
+pub const RUN_FRAME_NAME: &str = "Run";
+pub const RUNNING_FRAMES: u8 = 23;
 
 #[derive(Clone, Copy)]
 pub struct Running;
 
-const JUMP_SPEED: i16 = -25;
+pub const JUMP_SPEED: i16 = -25;
 
 impl RedHatBoyState<Running> {
     pub fn frame_name(&self) -> &str {
@@ -17,11 +17,7 @@ impl RedHatBoyState<Running> {
         log!("Running->Jumping");
 
         RedHatBoyState {
-            context: self
-                .context
-                .set_vertical_velocity(JUMP_SPEED)
-                .reset_frame()
-                .play_jump_sfx(),
+            context: self.context.set_vertical_velocity(JUMP_SPEED).reset_frame(),
             _state: Jumping {},
         }
     }
@@ -46,7 +42,7 @@ impl RedHatBoyState<Running> {
         log!("Running->Sliding");
 
         RedHatBoyState {
-            context: self.context.play_slide_sfx().reset_frame(),
+            context: self.context.reset_frame(),
             _state: Sliding {},
         }
     }
@@ -1,8 +1,8 @@
 use super::{falling::Falling, running::Running, RedHatBoyState};
 use crate::game::red_hat_boy::state_machine::RedHatBoyStateMachine;
 
-const SLIDE_FRAME_NAME: &str = "Slide";
-const SLIDING_FRAMES: u8 = 14;
+pub const SLIDE_FRAME_NAME: &str = "Slide";
+pub const SLIDING_FRAMES: u8 = 14;
 
 #[derive(Clone, Copy)]
 pub struct Sliding;
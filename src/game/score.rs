@@ -0,0 +1,181 @@
+use super::{
+    event_bus::Subscription,
+    event_queue::{EventSubscriber, GameEvent, GameEventKind},
+};
+use crate::engine::{
+    rect::Point,
+    renderer::{Renderer, TextOptions},
+};
+
+/// Scrolled pixels per meter of distance (and per point of score)
+const PIXELS_PER_METER: i32 = 50;
+const FRAMES_PER_SECOND: u32 = 60;
+
+/// Points added whenever a `GameEvent` of the given kind is processed during a run
+const BONUSES: [(GameEventKind, i32); 2] = [
+    (GameEventKind::DogHitMark, 5),
+    (GameEventKind::DogLandedOnPlatform, 10),
+];
+
+const HUD_POSITION: Point = Point { x: 10, y: 30 };
+const HUD_TEXT: TextOptions = TextOptions {
+    color: "#FFFFFF",
+    font: "20px 'Ken Future'",
+};
+
+/// Accumulates the score of the current run: distance walked plus bonuses
+#[derive(Debug, Default)]
+pub struct Score {
+    active: bool,
+    bonus: i32,
+    bonus_events: u32,
+    /// Pixels scrolled since the run started
+    distance: i32,
+    frames: u32,
+}
+
+/// Summary of a run, shown on the game over screen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RunStats {
+    pub bonus: i32,
+    pub bonus_events: u32,
+    pub meters: i32,
+    pub score: i32,
+    pub seconds: u32,
+}
+
+impl Score {
+    pub fn new() -> Self {
+        Score::default()
+    }
+
+    /// Add the distance walked during one frame of the run
+    pub fn advance(&mut self, distance: i16) {
+        if !self.active {
+            return;
+        }
+
+        self.distance += i32::from(distance);
+        self.frames += 1;
+    }
+
+    pub fn draw(&self, renderer: &Renderer) {
+        let text = format!("Score {}   {}m", self.total(), self.meters());
+        if let Err(err) = renderer.draw_text_ext(&text, &HUD_POSITION, HUD_TEXT) {
+            error!("Could not draw score {:#?}", err);
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.active = true;
+    }
+
+    pub fn stats(&self) -> RunStats {
+        RunStats {
+            bonus: self.bonus,
+            bonus_events: self.bonus_events,
+            meters: self.meters(),
+            score: self.total(),
+            seconds: self.frames / FRAMES_PER_SECOND,
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    pub fn total(&self) -> i32 {
+        self.meters() + self.bonus
+    }
+
+    /// Frames since the run started
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    pub fn meters(&self) -> i32 {
+        self.distance / PIXELS_PER_METER
+    }
+}
+
+impl RunStats {
+    pub fn summary_html(&self) -> String {
+        format!(
+            "<h2>Score {}</h2>\
+            <dl>\
+            <dt>Distance</dt><dd>{}m</dd>\
+            <dt>Time</dt><dd>{}s</dd>\
+            <dt>Bonus</dt><dd>{} ({} tricks)</dd>\
+            </dl>",
+            self.score, self.meters, self.seconds, self.bonus, self.bonus_events
+        )
+    }
+}
+
+impl EventSubscriber for Score {
+    fn name(&self) -> String {
+        "Score".to_string()
+    }
+
+    fn process_event(&mut self, event: &GameEvent) {
+        if !self.active {
+            return;
+        }
+
+        if let Some((_, points)) = BONUSES.iter().find(|(kind, _)| *kind == event.kind()) {
+            self.bonus += points;
+            self.bonus_events += 1;
+        }
+    }
+
+    fn subscriptions(&self) -> Vec<Subscription> {
+        BONUSES
+            .iter()
+            .map(|(kind, _)| Subscription::to(*kind))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance_and_bonuses_only_count_while_running() {
+        let mut score = Score::new();
+        score.advance(500);
+        score.process_event(&GameEvent::DogLandedOnGround);
+
+        score.start();
+        score.advance(100);
+        score.advance(100);
+        score.process_event(&GameEvent::DogLandedOnPlatform {
+            id: "p1".to_string(),
+            platform_top: 0,
+        });
+        score.process_event(&GameEvent::DogTooFar);
+        score.stop();
+        score.advance(500);
+
+        assert_eq!(
+            score.stats(),
+            RunStats {
+                bonus: 10,
+                bonus_events: 1,
+                meters: 4,
+                score: 14,
+                seconds: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn time_is_counted_in_frames() {
+        let mut score = Score::new();
+        score.start();
+        (0..150).for_each(|_| score.advance(4));
+
+        assert_eq!(score.stats().seconds, 2);
+        assert_eq!(score.stats().meters, 12);
+    }
+}
@@ -0,0 +1,81 @@
+use crate::browser;
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use std::cell::Cell;
+
+/// The source of all gameplay randomness during a run. Runs started from the
+/// same seed and played with the same inputs generate the same course.
+pub type RunRng = StdRng;
+
+pub const SEED_QUERY_PARAM: &str = "seed";
+
+thread_local! {
+    /// Seed every run starts from, when one has been chosen
+    static FIXED_SEED: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Use `seed` for every following run, or a fresh random seed per run if None
+pub fn fix_seed(seed: Option<u64>) {
+    FIXED_SEED.with(|fixed| fixed.set(seed));
+}
+
+/// Fix the seed from the page URL (e.g. `?seed=42`), if it has one
+pub fn fix_seed_from_url() {
+    match browser::query_param(SEED_QUERY_PARAM) {
+        Ok(Some(value)) => match parse_seed(&value) {
+            Some(seed) => fix_seed(Some(seed)),
+            None => error!("Ignoring seed {value}, it is not a whole number"),
+        },
+        Ok(None) => {}
+        Err(err) => error!("Could not read the seed from the URL {:#?}", err),
+    }
+}
+
+/// Seed for a new run: the fixed seed if there is one, otherwise a random one
+pub fn next_seed() -> u64 {
+    FIXED_SEED
+        .with(Cell::get)
+        .unwrap_or_else(|| thread_rng().gen())
+}
+
+pub fn parse_seed(value: &str) -> Option<u64> {
+    value.trim().parse().ok()
+}
+
+pub fn run_rng(seed: u64) -> RunRng {
+    StdRng::seed_from_u64(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_seed_is_used_for_every_run_until_cleared() {
+        fix_seed(Some(42));
+        assert_eq!(next_seed(), 42);
+        assert_eq!(next_seed(), 42);
+
+        fix_seed(None);
+        assert_ne!((next_seed(), next_seed()), (42, 42));
+    }
+
+    #[test]
+    fn seeds_are_whole_numbers() {
+        assert_eq!(parse_seed(" 1234 "), Some(1234));
+        assert_eq!(parse_seed("-1"), None);
+        assert_eq!(parse_seed("abc"), None);
+    }
+
+    #[test]
+    fn same_seed_draws_the_same_numbers() {
+        let draws = |seed| -> Vec<u32> {
+            run_rng(seed)
+                .sample_iter(rand::distributions::Standard)
+                .take(5)
+                .collect()
+        };
+
+        assert_eq!(draws(7), draws(7));
+        assert_ne!(draws(7), draws(8));
+    }
+}
@@ -1,191 +1,235 @@
+#[cfg(test)]
+mod analysis;
+pub mod definitions;
+pub mod registry;
+
 use super::{
     event_queue::EventPublisher,
-    obstacles::{barrier::Barrier, platform::Platform, Obstacle, ObstacleMarkFactory, ObstacleVec},
+    obstacles::{
+        barrier::Barrier, finish_line::FinishLine, platform::Platform, Obstacle,
+        ObstacleMarkFactory, ObstacleVec,
+    },
 };
 use crate::engine::{
     image::Image,
     rect::{Point, Rect},
     sprite_sheet::SpriteSheet,
 };
-use rand::{thread_rng, Rng};
+use definitions::{MarkSide, ObstacleSpec, SegmentDefinitions};
+use rand::Rng;
+use registry::SegmentPicker;
 use std::{cell::RefCell, rc::Rc};
 use web_sys::HtmlImageElement;
 
-const FIRST_PLATFORM: i16 = 240;
-const HIGH_PLATFORM: i16 = 375;
-const LOW_PLATFORM: i16 = 420;
-
-const STONE_ON_GROUND: i16 = 546;
-const STONE_ON_PLATFORM: i16 = 322;
-
-const STONE_AND_PLATFORM_SEGMENT_ID: i32 = 4;
-
 pub struct SegmentFactory {
+    definitions: SegmentDefinitions,
     event_publisher: EventPublisher,
     id: i32,
     obstacle_sheet: Rc<SpriteSheet>,
+    picker: SegmentPicker,
     stone_image: HtmlImageElement,
 }
 
 impl SegmentFactory {
+    /// `definitions` should already be validated, see `SegmentDefinitions::validate`
     pub fn new(
+        definitions: SegmentDefinitions,
         sprite_sheet: SpriteSheet,
         stone_image: HtmlImageElement,
         event_publisher: EventPublisher,
     ) -> Self {
+        let picker = SegmentPicker::after(&definitions.segments, first_segment(&definitions));
+
         SegmentFactory {
+            definitions,
             event_publisher,
             id: 0,
             obstacle_sheet: Rc::new(sprite_sheet),
+            picker,
             stone_image,
         }
     }
 
+    /// Build `obstacles`, positioned relative to `offset_x`, e.g. a segment
+    /// that isn't in the definitions yet
+    pub fn build(&mut self, obstacles: &[ObstacleSpec], offset_x: i16) -> ObstacleVec {
+        self.build_segment(UNNAMED_SEGMENT, obstacles, offset_x)
+    }
+
+    pub fn definitions(&self) -> &SegmentDefinitions {
+        &self.definitions
+    }
+
+    /// The finish line at the end of a level
+    pub fn finish_line(&mut self, x: i16) -> ObstacleVec {
+        self.id += 1;
+
+        vec![package(Box::new(FinishLine::new(
+            format!("f{}", self.id),
+            x,
+            self.event_publisher.clone(),
+        )))]
+    }
+
     pub fn first(&mut self) -> ObstacleVec {
         const OFFSET_X: i16 = 0;
 
-        if REPEAT >= 0 {
-            self.select(REPEAT, OFFSET_X)
-        } else {
-            self.select(STONE_AND_PLATFORM_SEGMENT_ID, OFFSET_X)
+        let first_segment = first_segment(&self.definitions);
+        self.picker = SegmentPicker::after(&self.definitions.segments, first_segment);
+
+        self.select(first_segment, OFFSET_X)
+    }
+
+    /// Build the segment called `name`, e.g. the next one of a level. Empty if
+    /// there is no such segment.
+    pub fn named(&mut self, name: &str, offset_x: i16) -> ObstacleVec {
+        match self.definitions.index_of(name) {
+            Some(segment) => self.select(segment, offset_x),
+            None => {
+                error!("No segment {name}");
+                vec![]
+            }
         }
     }
 
-    pub fn random(&mut self, offset_x: i16) -> ObstacleVec {
-        let mut rng = thread_rng();
-        let next_segment = if REPEAT >= 0 {
-            REPEAT
-        } else {
-            rng.gen_range(0..6)
+    /// Build the next segment for difficulty `level`, picked by the segments'
+    /// rules with `weight` scaling the chance of each segment by name. All
+    /// randomness is drawn from `rng`.
+    pub fn random(
+        &mut self,
+        offset_x: i16,
+        level: f32,
+        weight: impl Fn(&str) -> f32,
+        rng: &mut impl Rng,
+    ) -> ObstacleVec {
+        let next_segment = match REPEAT.and_then(|name| self.definitions.index_of(name)) {
+            Some(segment) => segment,
+            None => self
+                .picker
+                .pick(&self.definitions.segments, level, weight, rng),
         };
 
         self.select(next_segment, offset_x)
     }
 
-    fn create_floating_platform(&self, position: Point) -> Platform {
+    fn build_segment(
+        &mut self,
+        segment: &str,
+        obstacles: &[ObstacleSpec],
+        offset_x: i16,
+    ) -> ObstacleVec {
+        obstacles
+            .iter()
+            .flat_map(|obstacle| self.create(segment, obstacle, offset_x))
+            .collect()
+    }
+
+    fn create(&mut self, segment: &str, obstacle: &ObstacleSpec, offset_x: i16) -> ObstacleVec {
+        self.id += 1;
+
+        match obstacle {
+            ObstacleSpec::Platform {
+                marks,
+                sprite_set,
+                x,
+                y,
+            } => {
+                let platform = self.create_platform(
+                    segment,
+                    sprite_set,
+                    Point {
+                        x: offset_x + x,
+                        y: *y,
+                    },
+                );
+                with_marks(platform, marks)
+            }
+            ObstacleSpec::Stone { marks, x, y } => {
+                with_marks(self.create_stone(segment, offset_x + x, *y), marks)
+            }
+        }
+    }
+
+    fn create_platform(&self, segment: &str, sprite_set: &str, position: Point) -> Platform {
+        let (sprites, bounding_boxes) = match self.definitions.sprite_sets.get(sprite_set) {
+            Some(sprite_set) => (
+                sprite_set.sprites.iter().map(String::as_str).collect(),
+                sprite_set
+                    .bounding_boxes
+                    .iter()
+                    .map(|bounds| Rect::from(*bounds))
+                    .collect(),
+            ),
+            None => {
+                error!("No sprite set {sprite_set}, the platform will be invisible");
+                (vec![], vec![])
+            }
+        };
+
         Platform::new(
             format!("p{}", self.id),
+            segment.to_string(),
             self.obstacle_sheet.clone(),
             position,
-            &FLOATING_PLATFORM_SPRITES,
-            &FLOATING_PLATFORM_BOUNDING_BOXES,
+            &sprites,
+            &bounding_boxes,
             self.event_publisher.clone(),
         )
     }
 
-    fn create_stone(&self, x: i16, y: i16) -> Barrier {
+    fn create_stone(&self, segment: &str, x: i16, y: i16) -> Barrier {
         Barrier::new(
             format!("b{}", self.id),
+            segment.to_string(),
             Image::new(self.stone_image.clone(), Point { x, y }),
             self.event_publisher.clone(),
         )
     }
 
-    fn platform_and_stone(&self, offset_x: i16) -> ObstacleVec {
-        let platform = self.create_floating_platform(Point {
-            x: offset_x + 200,
-            y: HIGH_PLATFORM,
-        });
-        let stone = self.create_stone(offset_x + 350, STONE_ON_GROUND);
-
-        let mark_left = platform.mark_left();
-        let mark_right = platform.mark_right();
-
-        vec![
-            package(Box::new(mark_left)),
-            package(Box::new(platform)),
-            package(Box::new(stone)),
-            package(Box::new(mark_right)),
-        ]
-    }
-
-    fn platform_high(&self, offset_x: i16) -> ObstacleVec {
-        let platform = self.create_floating_platform(Point {
-            x: offset_x + 200,
-            y: HIGH_PLATFORM,
-        });
-
-        vec![package(Box::new(platform))]
-    }
-
-    fn platform_low(&self, offset_x: i16) -> ObstacleVec {
-        let platform = self.create_floating_platform(Point {
-            x: offset_x + 200,
-            y: LOW_PLATFORM,
-        });
-
-        vec![package(Box::new(platform))]
-    }
-
-    fn stone(&self, offset_x: i16) -> ObstacleVec {
-        let stone = self.create_stone(offset_x + 150, STONE_ON_GROUND);
-
-        let mark_left = stone.mark_left();
-        let mark_right = stone.mark_right();
+    fn select(&mut self, segment: usize, offset_x: i16) -> ObstacleVec {
+        let Some(spec) = self.definitions.segments.get(segment).cloned() else {
+            return vec![];
+        };
 
-        vec![
-            package(Box::new(mark_left)),
-            package(Box::new(stone)),
-            package(Box::new(mark_right)),
-        ]
+        self.build_segment(&spec.name, &spec.obstacles, offset_x)
     }
+}
 
-    fn stone_and_platform(&self, offset_x: i16) -> ObstacleVec {
-        let stone = self.create_stone(offset_x + 130, STONE_ON_GROUND);
-        let platform = self.create_floating_platform(Point {
-            x: offset_x + FIRST_PLATFORM,
-            y: LOW_PLATFORM,
-        });
-
-        let mark_left = stone.mark_left();
-        let mark_right_stone = stone.mark_right();
-        let mark_right = platform.mark_right();
-
-        vec![
-            package(Box::new(mark_left)),
-            package(Box::new(stone)),
-            package(Box::new(mark_right_stone)),
-            package(Box::new(platform)),
-            package(Box::new(mark_right)),
-        ]
-    }
+fn first_segment(definitions: &SegmentDefinitions) -> usize {
+    REPEAT
+        .and_then(|name| definitions.index_of(name))
+        .or_else(|| definitions.index_of(&definitions.first))
+        .unwrap_or(0)
+}
 
-    fn stone_on_platform(&self, offset_x: i16) -> ObstacleVec {
-        let stone = self.create_stone(offset_x + 390, STONE_ON_PLATFORM);
-        let platform = self.create_floating_platform(Point {
-            x: offset_x + 200,
-            y: HIGH_PLATFORM,
-        });
+fn package(obstacle: Box<dyn Obstacle>) -> Rc<RefCell<Box<dyn Obstacle>>> {
+    Rc::new(RefCell::new(obstacle))
+}
 
-        vec![package(Box::new(stone)), package(Box::new(platform))]
+/// `obstacle` packaged between the marks the dog should jump at
+fn with_marks<T>(obstacle: T, marks: &[MarkSide]) -> ObstacleVec
+where
+    T: Obstacle + ObstacleMarkFactory + 'static,
+{
+    let mut obstacles = Vec::new();
+    if marks.contains(&MarkSide::Left) {
+        obstacles.push(package(Box::new(obstacle.mark_left())));
     }
-
-    fn select(&mut self, segment: i32, offset_x: i16) -> ObstacleVec {
-        self.id += 1;
-
-        match segment {
-            0 => self.platform_and_stone(offset_x),
-            1 => self.platform_high(offset_x),
-            2 => self.platform_low(offset_x),
-            3 => self.stone(offset_x),
-            4 => self.stone_and_platform(offset_x),
-            5 => self.stone_on_platform(offset_x),
-
-            _ => vec![],
-        }
+    let mark_right = marks
+        .contains(&MarkSide::Right)
+        .then(|| obstacle.mark_right());
+    obstacles.push(package(Box::new(obstacle)));
+    if let Some(mark_right) = mark_right {
+        obstacles.push(package(Box::new(mark_right)));
     }
-}
 
-fn package(obstacle: Box<dyn Obstacle>) -> Rc<RefCell<Box<dyn Obstacle>>> {
-    Rc::new(RefCell::new(obstacle))
+    obstacles
 }
 
-const FLOATING_PLATFORM_BOUNDING_BOXES: [Rect; 3] = [
-    Rect::new_from_x_y(0, 0, 60, 54),
-    Rect::new_from_x_y(60, 0, 384 - (60 * 2), 93),
-    Rect::new_from_x_y(384 - 60, 0, 60, 54),
-];
-const FLOATING_PLATFORM_SPRITES: [&str; 3] = ["13.png", "14.png", "15.png"];
+/// Segment name in the ids of obstacles that weren't built from a named
+/// segment, e.g. in the editor
+const UNNAMED_SEGMENT: &str = "unnamed";
 
-// -1 means random segments. Set to 0, 3, 4, 5 for testing specific segments
-const REPEAT: i32 = -1;
+// None means picked segments. Set to Some("stone") etc. (a segment name in
+// segments.json) for testing specific segments
+const REPEAT: Option<&str> = None;
@@ -0,0 +1,760 @@
+//! Simulates the boy and the dog through segments to find segments, or
+//! sequences of segments, that can't be cleared. The simulation steps the
+//! game's own `KinematicBody` frame by frame the way the state machines do,
+//! without needing a browser.
+
+use super::{
+    definitions::{MarkSide, ObstacleSpec, SegmentDefinitions},
+    registry::SegmentSpec,
+};
+use crate::{
+    engine::{
+        physics::{sweep, Contact, ContactChange, KinematicBody},
+        rect::{Point, Rect},
+        sheet::{Cell, Sheet},
+    },
+    game::{
+        difficulty::Difficulty,
+        dog::{
+            self,
+            context::{
+                DOG_GROUND, DOG_HEIGHT, JUMPING_FRAMES as DOG_JUMPING_FRAMES,
+                JUMP_SPEED as DOG_JUMP_SPEED,
+            },
+            states::{
+                jumping::JUMPING_FRAME_PREFIX, running::RUNNING_FRAME_PREFIX, sprite_frame_name,
+            },
+        },
+        obstacles::{
+            barrier::{self, MARK_GAP},
+            obstacle_mark::mark_rect,
+            platform::RIGHT_MARK_OFFSET,
+        },
+        red_hat_boy::{
+            self,
+            context::{FLOOR, PLAYER_HEIGHT},
+            states::{
+                idle::STARTING_POINT,
+                jumping::{JUMPING_FRAMES, JUMP_FRAME_NAME},
+                running::{JUMP_SPEED, RUNNING_FRAMES, RUN_FRAME_NAME},
+                sliding::{SLIDE_FRAME_NAME, SLIDING_FRAMES},
+            },
+        },
+        HEIGHT, RUNNING_SPEED, TERMINAL_VELOCITY,
+    },
+};
+use std::collections::{HashMap, HashSet};
+
+/// Every way the boy can be controlled
+const ALL_INPUTS: [Input; 3] = [Input::None, Input::Jump, Input::Slide];
+/// Pixels of flat ground the boy and dog get before a course starts
+const RUN_UP: i16 = 300;
+
+/// How the boy can get through a segment
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Passability {
+    /// By jumping only, or doing nothing at all
+    pub by_jumping: bool,
+    /// By a route that lands on a platform
+    pub by_landing: bool,
+    /// Without pressing anything
+    pub by_running: bool,
+    /// By sliding only, or doing nothing at all
+    pub by_sliding: bool,
+    /// Any way at all
+    pub passable: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SegmentReport {
+    pub boy: Passability,
+    /// Barriers the dog runs into even though it jumps at their marks, e.g.
+    /// "stone at 130 (dog moving right)"
+    pub dog_hits: Vec<String>,
+    pub name: String,
+}
+
+pub struct SegmentAnalysis<'a> {
+    boy_frames: HashMap<(Motion, u8), Rect>,
+    definitions: &'a SegmentDefinitions,
+    dog_frames: HashMap<(bool, u8), Rect>,
+    /// Width and height of the stone image
+    stone: (i16, i16),
+}
+
+impl<'a> SegmentAnalysis<'a> {
+    pub fn new(
+        boy_sheet: &Sheet,
+        dog_sheet: &Sheet,
+        definitions: &'a SegmentDefinitions,
+        stone: (i16, i16),
+    ) -> Self {
+        let boy_animations = [
+            (Motion::Jumping, JUMP_FRAME_NAME, JUMPING_FRAMES),
+            (Motion::Running, RUN_FRAME_NAME, RUNNING_FRAMES),
+            (Motion::Sliding, SLIDE_FRAME_NAME, SLIDING_FRAMES),
+        ];
+        let boy_frames = boy_animations
+            .iter()
+            .flat_map(|(motion, animation, frames)| {
+                (0..=*frames).filter_map(move |frame| {
+                    let name = red_hat_boy::sprite_frame_name(animation, frame);
+                    boy_sheet.frames.get(&name).map(|cell| {
+                        (
+                            (*motion, frame),
+                            red_hat_boy::collision_box(sprite_box(cell)),
+                        )
+                    })
+                })
+            })
+            .collect();
+
+        let dog_animations = [
+            (true, JUMPING_FRAME_PREFIX, DOG_JUMPING_FRAMES),
+            (false, RUNNING_FRAME_PREFIX, dog::context::RUNNING_FRAMES),
+        ];
+        let dog_frames = dog_animations
+            .iter()
+            .flat_map(|(jumping, prefix, frames)| {
+                (0..=*frames).filter_map(move |frame| {
+                    dog_sheet
+                        .frames
+                        .get(&sprite_frame_name(prefix, frame))
+                        .map(|cell| ((*jumping, frame), sprite_box(cell)))
+                })
+            })
+            .collect();
+
+        SegmentAnalysis {
+            boy_frames,
+            definitions,
+            dog_frames,
+            stone,
+        }
+    }
+
+    /// Everything wrong with the segments at the start and the peak of
+    /// `difficulty`, one line per problem
+    pub fn problems(&self, difficulty: &Difficulty) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for level in [0.0, 1.0] {
+            let speed = RUNNING_SPEED + difficulty.speed_bonus(level);
+            let buffer = difficulty.obstacle_buffer(level);
+
+            for spec in &self.definitions.segments {
+                let report = self.segment(spec, speed);
+                if !report.boy.passable {
+                    problems.push(format!("{} can't be cleared at speed {speed}", spec.name));
+                }
+                problems.extend(
+                    report.dog_hits.iter().map(|hit| {
+                        format!("{}: the dog hits the {hit} at speed {speed}", spec.name)
+                    }),
+                );
+            }
+            problems.extend(
+                self.unwinnable_pairs(speed, buffer)
+                    .into_iter()
+                    .map(|(first, second)| {
+                        format!(
+                            "{first} then {second} can't be cleared at speed {speed} with a gap of {buffer}"
+                        )
+                    }),
+            );
+        }
+
+        problems
+    }
+
+    /// How the boy and dog fare on one segment with the course scrolling at `speed`
+    pub fn segment(&self, spec: &SegmentSpec, speed: i16) -> SegmentReport {
+        let course = self.course(&[spec], 0);
+        let cleared =
+            |inputs: &[Input], must_land| self.boy_clears(&course, speed, inputs, must_land);
+
+        SegmentReport {
+            boy: Passability {
+                by_jumping: cleared(&[Input::None, Input::Jump], false),
+                by_landing: cleared(&ALL_INPUTS, true),
+                by_running: cleared(&[Input::None], false),
+                by_sliding: cleared(&[Input::None, Input::Slide], false),
+                passable: cleared(&ALL_INPUTS, false),
+            },
+            dog_hits: self.dog_hits(&course, speed),
+            name: spec.name.clone(),
+        }
+    }
+
+    /// Pairs of segments the picker may put one after the other (with `buffer`
+    /// pixels between them) that the boy can't get through at `speed`
+    pub fn unwinnable_pairs(&self, speed: i16, buffer: i16) -> Vec<(String, String)> {
+        let segments = &self.definitions.segments;
+
+        segments
+            .iter()
+            .flat_map(|first| segments.iter().map(move |second| (first, second)))
+            .filter(|(first, second)| second.enters_from.contains(&first.exit))
+            .filter(|(first, second)| {
+                !self.boy_clears(
+                    &self.course(&[first, second], buffer),
+                    speed,
+                    &ALL_INPUTS,
+                    false,
+                )
+            })
+            .map(|(first, second)| (first.name.clone(), second.name.clone()))
+            .collect()
+    }
+
+    fn boy_box(&self, boy: &Boy, x: i16) -> Option<Rect> {
+        self.boy_frames.get(&(boy.motion, boy.frame)).map(|frame| {
+            offset(
+                frame,
+                Point {
+                    x,
+                    y: boy.body.position.y,
+                },
+            )
+        })
+    }
+
+    /// Search every sequence of inputs for one that gets the boy past the end of
+    /// `course` without being knocked out
+    fn boy_clears(&self, course: &Course, speed: i16, inputs: &[Input], must_land: bool) -> bool {
+        let mut frontier: HashSet<Boy> = HashSet::from([Boy::running()]);
+        let mut x = STARTING_POINT - RUN_UP;
+
+        while !frontier.is_empty() {
+            x += speed;
+            if x > course.end {
+                return frontier.iter().any(|boy| boy.landed || !must_land);
+            }
+
+            frontier = frontier
+                .iter()
+                .flat_map(|boy| inputs.iter().map(move |input| (boy, input)))
+                .filter_map(|(boy, input)| self.step_boy(*boy, *input, x, speed, course))
+                .collect();
+        }
+
+        false
+    }
+
+    /// Barriers with marks that the dog fails to clear when it jumps at them
+    fn dog_hits(&self, course: &Course, speed: i16) -> Vec<String> {
+        course
+            .solids
+            .iter()
+            .filter_map(|solid| match solid {
+                Solid::Stone { bounds, marks } => Some((bounds, marks)),
+                Solid::Platform { .. } => None,
+            })
+            .flat_map(|(bounds, marks)| {
+                let left = marks.contains(&MarkSide::Left).then(|| {
+                    let mark = mark_rect(barrier::left_mark_position(bounds));
+                    (!self.dog_clears(bounds, &mark, speed))
+                        .then(|| format!("stone at {} (dog moving right)", bounds.x()))
+                });
+                let right = marks.contains(&MarkSide::Right).then(|| {
+                    let mark = mark_rect(barrier::right_mark_position(bounds));
+                    (!self.dog_clears(bounds, &mark, -RUNNING_SPEED))
+                        .then(|| format!("stone at {} (dog moving left)", bounds.x()))
+                });
+
+                [left.flatten(), right.flatten()]
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Run the dog at `speed` (negative for leftwards) into `mark` and check
+    /// the jump it triggers carries it over `barrier`
+    fn dog_clears(&self, barrier: &Rect, mark: &Rect, speed: i16) -> bool {
+        let moving_left = speed < 0;
+        let x = if moving_left {
+            mark.right() + RUN_UP
+        } else {
+            mark.x() - RUN_UP
+        };
+        let mut body = KinematicBody::new(
+            Point { x, y: DOG_GROUND },
+            DOG_HEIGHT,
+            HEIGHT,
+            TERMINAL_VELOCITY,
+        );
+        body.velocity.x = speed;
+        let mut dog = Dog {
+            body,
+            frame: 0,
+            jumping: false,
+            on_mark: false,
+        };
+        let dog_box = |dog: &Dog| {
+            self.dog_frames
+                .get(&(dog.jumping, dog.frame))
+                .map(|frame| dog::collision_box(offset(frame, dog.body.position), moving_left))
+        };
+
+        loop {
+            // The mark's GameEvent is handled at the end of the frame's
+            // update, so the dog jumps before it next moves
+            if dog.on_mark && !dog.jumping {
+                dog.jumping = true;
+                dog.frame = 0;
+                dog.body.velocity.y = DOG_JUMP_SPEED;
+            }
+            let landed = dog.update();
+
+            let Some(bounding_box) = dog_box(&dog) else {
+                return false;
+            };
+            if bounding_box.intersects(barrier) {
+                return false;
+            }
+            dog.on_mark = bounding_box.intersects(mark);
+            // `DogLandedOnGround` is handled at the end of the update too
+            if landed && dog.jumping {
+                dog.jumping = false;
+                dog.frame = 0;
+            }
+
+            let passed = if moving_left {
+                bounding_box.right() < barrier.left()
+            } else {
+                bounding_box.left() > barrier.right()
+            };
+            if passed {
+                return true;
+            }
+        }
+    }
+
+    /// Lay `segments` out one after the other, `buffer` pixels apart
+    fn course(&self, segments: &[&SegmentSpec], buffer: i16) -> Course {
+        let mut course = Course {
+            end: 0,
+            solids: vec![],
+        };
+        let mut offset_x = 0;
+
+        for spec in segments {
+            let mut timeline = offset_x;
+            for obstacle in &spec.obstacles {
+                let solid = self.solid(obstacle, offset_x);
+                timeline = timeline.max(solid.timeline());
+                course.end = course.end.max(solid.right());
+                course.solids.push(solid);
+            }
+            offset_x = timeline + buffer;
+        }
+
+        course
+    }
+
+    fn solid(&self, obstacle: &ObstacleSpec, offset_x: i16) -> Solid {
+        match obstacle {
+            ObstacleSpec::Platform {
+                marks,
+                sprite_set,
+                x,
+                y,
+            } => {
+                let position = Point {
+                    x: offset_x + x,
+                    y: *y,
+                };
+                let boxes = self
+                    .definitions
+                    .sprite_sets
+                    .get(sprite_set)
+                    .map(|sprite_set| {
+                        sprite_set
+                            .bounding_boxes
+                            .iter()
+                            .map(|bounds| offset(&Rect::from(*bounds), position))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                Solid::Platform {
+                    boxes,
+                    marks: marks.clone(),
+                }
+            }
+            ObstacleSpec::Stone { marks, x, y } => Solid::Stone {
+                bounds: Rect::new_from_x_y(offset_x + x, *y, self.stone.0, self.stone.1),
+                marks: marks.clone(),
+            },
+        }
+    }
+
+    /// One frame of the boy's state machine followed by the obstacles' checks,
+    /// in the order `Walking.update` runs them. None if he is knocked out.
+    fn step_boy(
+        &self,
+        mut boy: Boy,
+        input: Input,
+        x: i16,
+        speed: i16,
+        course: &Course,
+    ) -> Option<Boy> {
+        if boy.motion == Motion::Running {
+            match input {
+                Input::Jump => {
+                    boy.motion = Motion::Jumping;
+                    boy.frame = 0;
+                    boy.body.velocity.y = JUMP_SPEED;
+                }
+                Input::Slide => {
+                    boy.motion = Motion::Sliding;
+                    boy.frame = 0;
+                }
+                Input::None => {}
+            }
+        }
+
+        boy.update();
+        let motion = Point {
+            x: speed,
+            y: boy.body.displacement().y,
+        };
+
+        if boy.motion == Motion::Sliding {
+            return Some(boy);
+        }
+        for solid in &course.solids {
+            let from = offset(
+                &self.boy_box(&boy, x)?,
+                Point {
+                    x: -motion.x,
+                    y: -motion.y,
+                },
+            );
+            match solid {
+                Solid::Platform { boxes, .. } => {
+                    let first_hit = boxes
+                        .iter()
+                        .filter_map(|b| sweep(&from, motion, b).map(|hit| (b, hit)))
+                        .min_by(|(_, first), (_, second)| first.time.total_cmp(&second.time));
+                    if let Some((landing, hit)) = first_hit {
+                        if hit.on_top()
+                            || (hit.time == 0.0 && boy.body.contact() == Contact::Platform)
+                        {
+                            boy.land_on(landing.top());
+                        } else {
+                            return None;
+                        }
+                    }
+                }
+                Solid::Stone { bounds, .. } => {
+                    if sweep(&from, motion, bounds).is_some() {
+                        return None;
+                    }
+                }
+            }
+        }
+
+        Some(boy)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Motion {
+    Jumping,
+    Running,
+    Sliding,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Input {
+    Jump,
+    None,
+    Slide,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct Boy {
+    body: KinematicBody,
+    frame: u8,
+    landed: bool,
+    motion: Motion,
+}
+
+impl Boy {
+    fn running() -> Self {
+        Boy {
+            body: KinematicBody::new(
+                Point { x: 0, y: FLOOR },
+                PLAYER_HEIGHT,
+                HEIGHT,
+                TERMINAL_VELOCITY,
+            ),
+            frame: 0,
+            landed: false,
+            motion: Motion::Running,
+        }
+    }
+
+    /// `land_on` of the boy's state, for a platform with its top at `top`
+    fn land_on(&mut self, top: i16) {
+        if self.motion == Motion::Jumping {
+            self.frame = 0;
+            self.motion = Motion::Running;
+        }
+        self.body.rest_on(top);
+        self.landed = true;
+    }
+
+    /// `update` of the boy's state
+    fn update(&mut self) {
+        let frame_count = match self.motion {
+            Motion::Jumping => JUMPING_FRAMES,
+            Motion::Running => RUNNING_FRAMES,
+            Motion::Sliding => SLIDING_FRAMES,
+        };
+        self.frame = if self.frame < frame_count {
+            self.frame + 1
+        } else {
+            0
+        };
+        self.body.step();
+
+        match self.motion {
+            Motion::Jumping if self.body.contact() == Contact::Ground => {
+                self.frame = 0;
+                self.motion = Motion::Running;
+                self.body.rest_on(HEIGHT);
+            }
+            Motion::Sliding if self.frame >= SLIDING_FRAMES => {
+                self.frame = 0;
+                self.motion = Motion::Running;
+            }
+            _ => {}
+        }
+    }
+}
+
+struct Dog {
+    body: KinematicBody,
+    frame: u8,
+    jumping: bool,
+    on_mark: bool,
+}
+
+impl Dog {
+    /// `update` of the dog's context. Returns true if it landed on the
+    /// ground.
+    fn update(&mut self) -> bool {
+        let frame_count = if self.jumping {
+            DOG_JUMPING_FRAMES
+        } else {
+            dog::context::RUNNING_FRAMES
+        };
+        self.frame = if self.frame < frame_count {
+            self.frame + 1
+        } else {
+            0
+        };
+        let landed = self.body.step() == Some(ContactChange::Landed(Contact::Ground));
+        self.body.position.x += self.body.velocity.x;
+
+        landed
+    }
+}
+
+struct Course {
+    /// Right edge of the rightmost solid obstacle
+    end: i16,
+    solids: Vec<Solid>,
+}
+
+enum Solid {
+    Platform {
+        boxes: Vec<Rect>,
+        marks: Vec<MarkSide>,
+    },
+    Stone {
+        bounds: Rect,
+        marks: Vec<MarkSide>,
+    },
+}
+
+impl Solid {
+    fn right(&self) -> i16 {
+        match self {
+            Solid::Platform { boxes, .. } => boxes.last().map_or(0, |b| b.right()),
+            Solid::Stone { bounds, .. } => bounds.right(),
+        }
+    }
+
+    /// Where `Walk`'s timeline ends up after this obstacle and its marks
+    fn timeline(&self) -> i16 {
+        let marks = match self {
+            Solid::Platform { marks, .. } | Solid::Stone { marks, .. } => marks,
+        };
+        if !marks.contains(&MarkSide::Right) {
+            return self.right();
+        }
+
+        match self {
+            Solid::Platform { .. } => self.right() + RIGHT_MARK_OFFSET + 1,
+            Solid::Stone { .. } => self.right() + MARK_GAP + 1,
+        }
+    }
+}
+
+/// Where a frame of `cell` is drawn relative to its sprite's position
+fn sprite_box(cell: &Cell) -> Rect {
+    Rect::new_from_x_y(
+        cell.sprite_source_size.x,
+        cell.sprite_source_size.y,
+        cell.frame.w,
+        cell.frame.h,
+    )
+}
+
+fn offset(rect: &Rect, by: Point) -> Rect {
+    Rect::new_from_x_y(rect.x() + by.x, rect.y() + by.y, rect.width, rect.height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::levels::Levels;
+
+    fn sheet(json: &str) -> Sheet {
+        serde_json::from_str(json).unwrap()
+    }
+
+    /// Width and height from the PNG header
+    fn stone_size() -> (i16, i16) {
+        let png = include_bytes!("../../../static/Stone.png");
+        let dimension = |at: usize| u32::from_be_bytes(png[at..at + 4].try_into().unwrap()) as i16;
+
+        (dimension(16), dimension(20))
+    }
+
+    fn with_analysis(test: impl Fn(&SegmentAnalysis, &SegmentDefinitions)) {
+        let boy_sheet = sheet(include_str!("../../../static/rhb.json"));
+        let dog_sheet = sheet(include_str!("../../../static/dog.json"));
+        let definitions = SegmentDefinitions::built_in();
+        let analysis = SegmentAnalysis::new(&boy_sheet, &dog_sheet, &definitions, stone_size());
+
+        test(&analysis, &definitions);
+    }
+
+    fn report(
+        analysis: &SegmentAnalysis,
+        definitions: &SegmentDefinitions,
+        name: &str,
+    ) -> SegmentReport {
+        let spec = &definitions.segments[definitions.index_of(name).unwrap()];
+
+        analysis.segment(spec, RUNNING_SPEED)
+    }
+
+    #[test]
+    fn every_built_in_segment_can_be_cleared() {
+        with_analysis(|analysis, definitions| {
+            for spec in &definitions.segments {
+                assert!(
+                    analysis.segment(spec, RUNNING_SPEED).boy.passable,
+                    "{} can't be cleared",
+                    spec.name
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn stones_must_be_jumped_and_low_platforms_climbed() {
+        with_analysis(|analysis, definitions| {
+            let stone = report(analysis, definitions, "stone").boy;
+            assert!(!stone.by_running);
+            assert!(stone.by_jumping);
+
+            let platform_low = report(analysis, definitions, "platform_low").boy;
+            assert!(!platform_low.by_running);
+            assert!(platform_low.by_landing);
+        });
+    }
+
+    #[test]
+    fn a_stone_on_a_platform_is_passed_by_landing_on_the_platform() {
+        with_analysis(|analysis, definitions| {
+            assert!(
+                report(analysis, definitions, "stone_on_platform")
+                    .boy
+                    .by_landing
+            );
+        });
+    }
+
+    #[test]
+    fn dog_clears_stones_with_marks() {
+        with_analysis(|analysis, definitions| {
+            for name in ["stone", "stone_and_platform"] {
+                assert_eq!(
+                    report(analysis, definitions, name).dog_hits,
+                    Vec::<String>::new()
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn every_built_in_level_can_be_cleared() {
+        with_analysis(|analysis, definitions| {
+            let difficulty: Difficulty =
+                serde_json::from_str(include_str!("../../../static/difficulty.json")).unwrap();
+
+            for level in Levels::built_in().levels {
+                let specs: Vec<&SegmentSpec> = level
+                    .segments
+                    .iter()
+                    .map(|name| &definitions.segments[definitions.index_of(name).unwrap()])
+                    .collect();
+                for pair in specs.windows(2) {
+                    for difficulty_level in [0.0, 1.0] {
+                        let speed = RUNNING_SPEED + difficulty.speed_bonus(difficulty_level);
+                        let course =
+                            analysis.course(pair, difficulty.obstacle_buffer(difficulty_level));
+
+                        assert!(
+                            analysis.boy_clears(&course, speed, &ALL_INPUTS, false),
+                            "{}: {} then {} can't be cleared at speed {speed}",
+                            level.name,
+                            pair[0].name,
+                            pair[1].name
+                        );
+                    }
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn shipped_difficulty_has_no_unwinnable_segments_or_sequences() {
+        with_analysis(|analysis, _| {
+            let difficulty: Difficulty =
+                serde_json::from_str(include_str!("../../../static/difficulty.json")).unwrap();
+
+            assert_eq!(analysis.problems(&difficulty), Vec::<String>::new());
+        });
+    }
+
+    #[test]
+    fn flags_a_stone_too_wide_to_jump() {
+        let boy_sheet = sheet(include_str!("../../../static/rhb.json"));
+        let dog_sheet = sheet(include_str!("../../../static/dog.json"));
+        let definitions = SegmentDefinitions::built_in();
+        let analysis = SegmentAnalysis::new(&boy_sheet, &dog_sheet, &definitions, (400, 54));
+        let stone = &definitions.segments[definitions.index_of("stone").unwrap()];
+
+        let report = analysis.segment(stone, RUNNING_SPEED);
+
+        assert!(!report.boy.passable);
+        assert_eq!(report.dog_hits.len(), 2);
+    }
+}
@@ -0,0 +1,165 @@
+use super::registry::SegmentSpec;
+use crate::{browser, engine::rect::Rect};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const SEGMENTS_CONFIG: &str = "segments.json";
+
+/// Every segment the factory can build, as loaded from `SEGMENTS_CONFIG`
+#[derive(Clone, Debug, Deserialize)]
+pub struct SegmentDefinitions {
+    /// Name of the segment every run starts with
+    pub first: String,
+    pub segments: Vec<SegmentSpec>,
+    /// Platform sprites and bounding boxes, by name
+    pub sprite_sets: HashMap<String, SpriteSet>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SpriteSet {
+    /// Relative to the platform's top left corner
+    pub bounding_boxes: Vec<Bounds>,
+    /// Tiles from the obstacle sprite sheet, drawn left to right
+    pub sprites: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Bounds {
+    pub height: i16,
+    pub width: i16,
+    pub x: i16,
+    pub y: i16,
+}
+
+/// One obstacle of a segment. `x` is relative to the start of the segment and
+/// `y` is from the top of the canvas.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ObstacleSpec {
+    Platform {
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        marks: Vec<MarkSide>,
+        sprite_set: String,
+        x: i16,
+        y: i16,
+    },
+    Stone {
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        marks: Vec<MarkSide>,
+        x: i16,
+        y: i16,
+    },
+}
+
+/// Which side of an obstacle gets an `ObstacleMark` for the dog to jump at
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkSide {
+    Left,
+    Right,
+}
+
+impl From<Bounds> for Rect {
+    fn from(bounds: Bounds) -> Self {
+        Rect::new_from_x_y(bounds.x, bounds.y, bounds.width, bounds.height)
+    }
+}
+
+impl SegmentDefinitions {
+    /// Load and check the segment definitions at `json_path`, falling back to
+    /// the definitions built into the game if they can't be used
+    pub async fn load(json_path: &str) -> Self {
+        match SegmentDefinitions::fetch(json_path).await {
+            Ok(definitions) => definitions,
+            Err(err) => {
+                error!("Using the built-in segments {:#?}", err);
+                SegmentDefinitions::built_in()
+            }
+        }
+    }
+
+    /// The segments shipped with the game
+    pub fn built_in() -> Self {
+        serde_json::from_str(include_str!("../../../static/segments.json"))
+            .expect("Built-in segments should deserialize")
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.segments.iter().position(|spec| spec.name == name)
+    }
+
+    /// Every tile sprite name a segment can request from the obstacle sheet
+    pub fn required_sprites(&self) -> Vec<String> {
+        self.sprite_sets
+            .values()
+            .flat_map(|sprite_set| sprite_set.sprites.iter().cloned())
+            .collect()
+    }
+
+    /// Check the definitions refer only to segments and sprite sets that exist
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+        if self.index_of(&self.first).is_none() {
+            problems.push(format!("first segment {} is not defined", self.first));
+        }
+        for spec in &self.segments {
+            if spec.enters_from.is_empty() {
+                problems.push(format!("{} can never be entered", spec.name));
+            }
+            for obstacle in &spec.obstacles {
+                if let ObstacleSpec::Platform { sprite_set, .. } = obstacle {
+                    if !self.sprite_sets.contains_key(sprite_set) {
+                        problems.push(format!(
+                            "{} uses undefined sprite set {}",
+                            spec.name, sprite_set
+                        ));
+                    }
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Segment validation failed\n{}",
+                problems.join("\n")
+            ))
+        }
+    }
+
+    async fn fetch(json_path: &str) -> Result<Self> {
+        let json = browser::fetch_json(json_path).await?;
+        let definitions = serde_wasm_bindgen::from_value::<SegmentDefinitions>(json)
+            .map_err(|err| anyhow!("Error deserializing {} {:#?}", json_path, err))?;
+        definitions.validate()?;
+
+        Ok(definitions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_segments_are_valid() {
+        let definitions = SegmentDefinitions::built_in();
+
+        assert!(definitions.validate().is_ok());
+        assert_eq!(definitions.segments.len(), 6);
+    }
+
+    #[test]
+    fn undefined_names_fail_validation() {
+        let mut definitions = SegmentDefinitions::built_in();
+        definitions.first = "nowhere".to_string();
+        definitions.sprite_sets.clear();
+
+        let message = definitions.validate().unwrap_err().to_string();
+
+        assert!(message.contains("first segment nowhere is not defined"));
+        assert!(message.contains("platform_high uses undefined sprite set floating_platform"));
+    }
+}
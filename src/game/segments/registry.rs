@@ -0,0 +1,180 @@
+use super::definitions::ObstacleSpec;
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use serde::{Deserialize, Serialize};
+
+/// Where the boy can be when one segment hands over to the next
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Elevation {
+    Ground,
+    Platform,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SegmentSpec {
+    /// Segments that must be picked before this one can be picked again
+    pub cooldown: u32,
+    /// Elevations the previous segment may exit at for this one to be picked
+    pub enters_from: Vec<Elevation>,
+    /// Elevation the boy is left at when the segment is cleared
+    pub exit: Elevation,
+    /// Difficulty level (0.0 to 1.0) a run must reach before the segment shows up
+    pub min_difficulty: f32,
+    pub name: String,
+    /// Obstacles making up the segment, positioned relative to its start
+    pub obstacles: Vec<ObstacleSpec>,
+    /// Relative chance of being picked, before the difficulty curve's weighting
+    pub weight: f32,
+}
+
+/// Picks the sequence of segments, keeping track of what was picked so far so
+/// that each pick respects the rules of the segments' specs
+#[derive(Debug)]
+pub struct SegmentPicker {
+    /// Picks left before each segment is off cooldown
+    cooldowns: Vec<u32>,
+    last_exit: Elevation,
+}
+
+impl SegmentPicker {
+    /// A picker following on from `segments[segment]`
+    pub fn after(segments: &[SegmentSpec], segment: usize) -> Self {
+        let mut picker = SegmentPicker {
+            cooldowns: vec![0; segments.len()],
+            last_exit: Elevation::Ground,
+        };
+        picker.picked(segments, segment);
+
+        picker
+    }
+
+    /// Pick the index of the next segment at difficulty `level`. Each segment
+    /// the rules allow is picked with the chance its spec's weight times
+    /// `weight(name)` gives it. If the weights rule everything out, any segment
+    /// enterable from where the last one exited is picked.
+    pub fn pick(
+        &mut self,
+        segments: &[SegmentSpec],
+        level: f32,
+        weight: impl Fn(&str) -> f32,
+        rng: &mut impl Rng,
+    ) -> usize {
+        let weights = segments.iter().enumerate().map(|(i, spec)| {
+            if self.allows(segments, i, level) {
+                spec.weight * weight(&spec.name)
+            } else {
+                0.0
+            }
+        });
+        let segment = match WeightedIndex::new(weights) {
+            Ok(weights) => weights.sample(rng),
+            Err(err) => {
+                error!("No segment fits the rules and weights {:#?}", err);
+                let enterable: Vec<usize> = (0..segments.len())
+                    .filter(|i| segments[*i].enters_from.contains(&self.last_exit))
+                    .collect();
+                if enterable.is_empty() {
+                    rng.gen_range(0..segments.len())
+                } else {
+                    enterable[rng.gen_range(0..enterable.len())]
+                }
+            }
+        };
+        self.picked(segments, segment);
+
+        segment
+    }
+
+    fn allows(&self, segments: &[SegmentSpec], segment: usize, level: f32) -> bool {
+        let spec = &segments[segment];
+
+        self.cooldowns[segment] == 0
+            && level >= spec.min_difficulty
+            && spec.enters_from.contains(&self.last_exit)
+    }
+
+    fn picked(&mut self, segments: &[SegmentSpec], segment: usize) {
+        self.cooldowns
+            .iter_mut()
+            .for_each(|cooldown| *cooldown = cooldown.saturating_sub(1));
+        self.cooldowns[segment] = segments[segment].cooldown;
+        self.last_exit = segments[segment].exit;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::segments::definitions::SegmentDefinitions;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn segments() -> Vec<SegmentSpec> {
+        SegmentDefinitions::built_in().segments
+    }
+
+    fn index_of(segments: &[SegmentSpec], name: &str) -> usize {
+        segments.iter().position(|spec| spec.name == name).unwrap()
+    }
+
+    fn picks(level: f32, count: usize) -> Vec<usize> {
+        let segments = segments();
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut picker = SegmentPicker::after(&segments, index_of(&segments, "platform_high"));
+
+        (0..count)
+            .map(|_| picker.pick(&segments, level, |_| 1.0, &mut rng))
+            .collect()
+    }
+
+    #[test]
+    fn segments_are_not_picked_again_while_on_cooldown() {
+        let sequence = picks(1.0, 500);
+
+        for (i, spec) in segments().iter().enumerate() {
+            let positions: Vec<usize> = sequence
+                .iter()
+                .enumerate()
+                .filter(|(_, segment)| **segment == i)
+                .map(|(position, _)| position)
+                .collect();
+            assert!(
+                positions
+                    .windows(2)
+                    .all(|pair| pair[1] - pair[0] > spec.cooldown as usize),
+                "{} repeated within its cooldown",
+                spec.name
+            );
+        }
+    }
+
+    #[test]
+    fn each_segment_is_enterable_from_the_previous_exit() {
+        let segments = segments();
+        let sequence = picks(1.0, 500);
+
+        assert!(sequence.windows(2).all(|pair| {
+            segments[pair[1]]
+                .enters_from
+                .contains(&segments[pair[0]].exit)
+        }));
+    }
+
+    #[test]
+    fn segments_wait_for_their_minimum_difficulty() {
+        let stone_on_platform = index_of(&segments(), "stone_on_platform");
+
+        assert!(!picks(0.0, 500).contains(&stone_on_platform));
+        assert!(picks(1.0, 500).contains(&stone_on_platform));
+    }
+
+    #[test]
+    fn falls_back_to_an_enterable_segment_when_weights_rule_everything_out() {
+        let segments = segments();
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut picker = SegmentPicker::after(&segments, index_of(&segments, "stone_and_platform"));
+
+        let segment = picker.pick(&segments, 0.0, |_| 0.0, &mut rng);
+
+        assert!(segments[segment].enters_from.contains(&Elevation::Platform));
+    }
+}
@@ -0,0 +1,369 @@
+use super::{
+    event_bus::{self, Subscription},
+    event_queue::{EventSubscriber, GameEvent, GameEventKind},
+    WIDTH,
+};
+use crate::{
+    browser,
+    engine::{
+        audio::{self, Audio, Sound},
+        rect::Rect,
+        sfx::{Sfx, SfxConfig},
+        synth::{Tone, Waveform},
+    },
+};
+use anyhow::Result;
+use std::collections::HashMap;
+
+pub const BARK: &str = "bark";
+pub const JUMP: &str = "jump";
+pub const KNOCK_OUT: &str = "knock_out";
+pub const SLIDE: &str = "slide";
+pub const THUD: &str = "thud";
+
+/// Turns stereo panning off with `?panning=off`, e.g. for mono speakers
+const PANNING_QUERY_PARAM: &str = "panning";
+
+/// Every sound effect in the game. To add a sound, add an entry here and play
+/// it on an event by adding a row to `AUDIO_REACTIONS`.
+const SOUND_EFFECTS: [SoundEffect; 5] = [
+    // No recorded bark yet, so a pitched up jump stands in for it
+    SoundEffect {
+        config: SfxConfig {
+            cooldown: 1.5,
+            max_voices: 1,
+            pitch: 1.8,
+            pitch_jitter: 0.1,
+            volume: 0.6,
+            volume_jitter: 0.1,
+        },
+        fallback: Tone {
+            duration: 0.12,
+            end_frequency: 300.0,
+            frequency: 500.0,
+            volume: 0.3,
+            waveform: Waveform::Square,
+        },
+        files: &["SFX_Jump_23.mp3", "SFX_Jump_23.wav"],
+        name: BARK,
+    },
+    SoundEffect {
+        config: SfxConfig {
+            cooldown: 0.1,
+            max_voices: 2,
+            pitch: 1.0,
+            pitch_jitter: 0.08,
+            volume: 1.0,
+            volume_jitter: 0.1,
+        },
+        fallback: Tone {
+            duration: 0.15,
+            end_frequency: 600.0,
+            frequency: 300.0,
+            volume: 0.3,
+            waveform: Waveform::Square,
+        },
+        files: &["SFX_Jump_23.mp3", "SFX_Jump_23.wav"],
+        name: JUMP,
+    },
+    SoundEffect {
+        config: SfxConfig {
+            cooldown: 1.0,
+            max_voices: 1,
+            pitch: 1.0,
+            pitch_jitter: 0.0,
+            volume: 1.0,
+            volume_jitter: 0.0,
+        },
+        fallback: Tone {
+            duration: 0.6,
+            end_frequency: 80.0,
+            frequency: 400.0,
+            volume: 0.3,
+            waveform: Waveform::Sawtooth,
+        },
+        files: &["vgdeathsound.ogg", "vgdeathsound.wav"],
+        name: KNOCK_OUT,
+    },
+    SoundEffect {
+        config: SfxConfig {
+            cooldown: 0.15,
+            max_voices: 1,
+            pitch: 1.0,
+            pitch_jitter: 0.05,
+            volume: 1.0,
+            volume_jitter: 0.1,
+        },
+        fallback: Tone {
+            duration: 0.25,
+            end_frequency: 120.0,
+            frequency: 200.0,
+            volume: 0.3,
+            waveform: Waveform::Triangle,
+        },
+        files: &["slide.wav"],
+        name: SLIDE,
+    },
+    SoundEffect {
+        config: SfxConfig {
+            cooldown: 0.2,
+            max_voices: 2,
+            pitch: 0.5,
+            pitch_jitter: 0.1,
+            volume: 0.5,
+            volume_jitter: 0.1,
+        },
+        fallback: Tone {
+            duration: 0.12,
+            end_frequency: 50.0,
+            frequency: 120.0,
+            volume: 0.5,
+            waveform: Waveform::Sine,
+        },
+        files: &["slide.wav"],
+        name: THUD,
+    },
+];
+
+/// Background music files, preferred first. `FALLBACK_MUSIC` is looped
+/// instead if none of them load.
+const BACKGROUND_MUSIC: [&str; 1] = ["background_song.mp3"];
+
+/// A synthesized arpeggio looped when the background music can't be loaded
+const FALLBACK_MUSIC: [Tone; 8] = [
+    note(261.63),
+    note(329.63),
+    note(392.0),
+    note(523.25),
+    note(392.0),
+    note(329.63),
+    note(293.66),
+    note(246.94),
+];
+
+/// A quarter second of `frequency` for `FALLBACK_MUSIC`
+const fn note(frequency: f32) -> Tone {
+    Tone {
+        duration: 0.25,
+        end_frequency: frequency,
+        frequency,
+        volume: 0.08,
+        waveform: Waveform::Triangle,
+    }
+}
+
+struct SoundEffect {
+    config: SfxConfig,
+    /// Synthesized instead when none of the `files` can be loaded
+    fallback: Tone,
+    /// The same sound in different formats, preferred first. The compressed
+    /// files fall back to a WAV, which every browser can decode.
+    files: &'static [&'static str],
+    name: &'static str,
+}
+
+/// Sound effect played (panned to its emitter) whenever a `GameEvent` of the
+/// given kind is processed. Add a row to give an event an audio reaction; no
+/// game object code changes.
+const AUDIO_REACTIONS: [(GameEventKind, &str, Emitter); 6] = [
+    (GameEventKind::BoyJumped, JUMP, Emitter::Boy),
+    (GameEventKind::BoyKnockedOut, KNOCK_OUT, Emitter::Boy),
+    (GameEventKind::BoySlid, SLIDE, Emitter::Boy),
+    (GameEventKind::DogLandedOnGround, THUD, Emitter::Dog),
+    (GameEventKind::DogLandedOnPlatform, THUD, Emitter::Dog),
+    (GameEventKind::DogTooFar, BARK, Emitter::Dog),
+];
+
+/// Where a sound comes from, for stereo panning
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Emitter {
+    Boy,
+    Dog,
+}
+
+/// Load every sound effect and start the background music. Without audio
+/// (e.g. the browser can't create an `AudioContext`) the game plays silently
+/// instead of not starting.
+pub async fn load() -> Sfx {
+    match load_with_audio().await {
+        Ok(sfx) => sfx,
+        Err(err) => {
+            error!("Playing without sound {:#?}", err);
+            Sfx::silent()
+        }
+    }
+}
+
+async fn load_with_audio() -> Result<Sfx> {
+    let audio = Audio::new()?;
+    let sfx = Sfx::new(audio.clone());
+    let mut loaded: HashMap<&[&str], Sound> = HashMap::new();
+    for effect in SOUND_EFFECTS {
+        let sound = match loaded.get(effect.files) {
+            Some(sound) => sound.clone(),
+            None => {
+                audio
+                    .load_sound_or_fallback(effect.files, &effect.fallback)
+                    .await?
+            }
+        };
+        loaded.insert(effect.files, sound.clone());
+        sfx.register(effect.name, sound, effect.config);
+    }
+    play_background_music(&audio).await;
+
+    Ok(sfx)
+}
+
+/// Start the background music, or `FALLBACK_MUSIC` if it can't be loaded.
+/// It isn't tied to an `Emitter`, so it plays centered.
+async fn play_background_music(audio: &Audio) {
+    let music = match audio.load_first_sound(&BACKGROUND_MUSIC).await {
+        Ok(music) => Ok(music),
+        Err(err) => {
+            error!("{:#?}\nUsing synthesized music", err);
+            audio.synthesize_sequence(&FALLBACK_MUSIC)
+        }
+    };
+    let played = music.and_then(|music| audio.play_looping_sound(&music));
+
+    if let Err(err) = played {
+        error!("Playing without background music {:#?}", err);
+    }
+}
+
+/// Plays the sound effects configured in `AUDIO_REACTIONS` as `GameEvent`s
+/// are processed
+pub struct AudioReactions {
+    panning: bool,
+    positions: HashMap<Emitter, i16>,
+    sfx: Sfx,
+}
+
+impl AudioReactions {
+    /// Reactions panned by their emitter's position, or all centered if
+    /// `panning` is off
+    pub fn new(sfx: Sfx, panning: bool) -> Self {
+        AudioReactions {
+            panning,
+            positions: HashMap::new(),
+            sfx,
+        }
+    }
+
+    /// Record where `emitter` is on the screen. Its sounds are panned by the
+    /// middle of its `bounding_box`.
+    pub fn track(&mut self, emitter: Emitter, bounding_box: &Rect) {
+        self.positions.insert(emitter, bounding_box.center_x());
+    }
+
+    /// Centered with panning off, or until the emitter's position is first
+    /// tracked
+    fn pan(&self, emitter: Emitter) -> f32 {
+        self.positions
+            .get(&emitter)
+            .filter(|_| self.panning)
+            .map_or(0.0, |x| audio::pan_for(*x, WIDTH))
+    }
+}
+
+impl EventSubscriber for AudioReactions {
+    fn name(&self) -> String {
+        "AudioReactions".to_string()
+    }
+
+    fn process_event(&mut self, event: &GameEvent) {
+        if let Some((sound, emitter)) = reaction_for(event.kind()) {
+            if let Err(err) = self.sfx.play_at(sound, self.pan(emitter)) {
+                log!("Error playing '{sound}' for {event:?} {:#?}", err);
+            }
+        }
+    }
+
+    /// Sounds play once everything else has reacted to the event
+    fn subscriptions(&self) -> Vec<Subscription> {
+        AUDIO_REACTIONS
+            .iter()
+            .map(|(kind, _, _)| Subscription::to(*kind).with_priority(event_bus::LAST))
+            .collect()
+    }
+}
+
+/// False if the page URL turns panning off (`?panning=off`)
+pub fn panning_from_url() -> bool {
+    match browser::query_param(PANNING_QUERY_PARAM) {
+        Ok(param) => panning_enabled(param.as_deref()),
+        Err(err) => {
+            error!("Could not read the panning setting from the URL {:#?}", err);
+            true
+        }
+    }
+}
+
+fn panning_enabled(param: Option<&str>) -> bool {
+    param != Some("off")
+}
+
+fn reaction_for(kind: GameEventKind) -> Option<(&'static str, Emitter)> {
+    AUDIO_REACTIONS
+        .iter()
+        .find(|(event_kind, _, _)| *event_kind == kind)
+        .map(|(_, sound, emitter)| (*sound, *emitter))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_reaction_plays_a_registered_sound() {
+        for (kind, sound, _) in AUDIO_REACTIONS {
+            assert!(
+                SOUND_EFFECTS.iter().any(|effect| effect.name == sound),
+                "{kind:?} reacts with unregistered sound '{sound}'"
+            );
+        }
+    }
+
+    #[test]
+    fn every_sound_falls_back_to_a_wav_that_ships() {
+        for effect in SOUND_EFFECTS {
+            assert!(
+                effect
+                    .files
+                    .last()
+                    .is_some_and(|file| file.ends_with(".wav")),
+                "{:?} has no WAV to fall back to",
+                effect.files
+            );
+        }
+        let files = SOUND_EFFECTS
+            .iter()
+            .flat_map(|effect| effect.files)
+            .chain(&BACKGROUND_MUSIC);
+        for file in files {
+            let path = format!("{}/static/{file}", env!("CARGO_MANIFEST_DIR"));
+            assert!(std::path::Path::new(&path).exists(), "{file} is missing");
+        }
+    }
+
+    #[test]
+    fn panning_is_on_unless_turned_off() {
+        assert!(panning_enabled(None));
+        assert!(panning_enabled(Some("on")));
+        assert!(!panning_enabled(Some("off")));
+    }
+
+    #[test]
+    fn events_without_a_reaction_are_silent() {
+        assert_eq!(
+            reaction_for(GameEventKind::DogTooFar),
+            Some((BARK, Emitter::Dog))
+        );
+        assert_eq!(
+            reaction_for(GameEventKind::BoyJumped),
+            Some((JUMP, Emitter::Boy))
+        );
+        assert_eq!(reaction_for(GameEventKind::DogHitMark), None);
+    }
+}
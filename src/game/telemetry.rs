@@ -0,0 +1,788 @@
+use super::{
+    event_bus::Subscription,
+    event_queue::{EventSubscriber, GameEvent, GameEventKind},
+    score::RunStats,
+};
+use crate::{browser, engine::storage::Storage};
+use anyhow::{anyhow, Result};
+use futures::channel::oneshot;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+pub const TELEMETRY_QUERY_PARAM: &str = "telemetry";
+const STORAGE_KEY: &str = "walk_the_dog.telemetry";
+/// Most runs sent in one request
+const BATCH_SIZE: usize = 10;
+/// Most runs kept while they can't be sent. Past it the oldest are dropped.
+const MAX_BUFFERED: usize = 50;
+/// Most events reported per run. Later ones are still counted.
+const MAX_RUN_EVENTS: usize = 500;
+/// Frames to wait after a failed send before trying again. Doubles with
+/// every failure in a row, up to `MAX_RETRY_FRAMES`.
+const RETRY_FRAMES: u32 = 5 * 60;
+const MAX_RETRY_FRAMES: u32 = 5 * 60 * 60;
+
+/// Events listed in each `RunReport`, on top of its counts
+const REPORTED_EVENTS: [GameEventKind; 7] = [
+    GameEventKind::BoyClearedBarrier,
+    GameEventKind::BoyHitsObstacle,
+    GameEventKind::BoyJumped,
+    GameEventKind::BoyLandedOnPlatform,
+    GameEventKind::BoySlid,
+    GameEventKind::DogTooFar,
+    GameEventKind::LevelFinished,
+];
+
+/// Hosts telemetry may be sent to, so a link can't make the game report runs
+/// anywhere but the tester's own machine
+const LOOPBACK_HOSTS: [&str; 3] = ["localhost", "127.0.0.1", "[::1]"];
+
+/// The endpoint in the page URL (e.g. `?telemetry=http://localhost:8080/runs`).
+/// Telemetry is off unless there is one.
+pub fn endpoint_from_url() -> Option<String> {
+    match browser::query_param(TELEMETRY_QUERY_PARAM) {
+        Ok(Some(value)) => {
+            let endpoint = parse_endpoint(&value);
+            if endpoint.is_none() {
+                error!(
+                    "Ignoring telemetry endpoint {value}, it is not an http(s) URL on localhost"
+                );
+            }
+            endpoint
+        }
+        Ok(None) => None,
+        Err(err) => {
+            error!(
+                "Could not read the telemetry endpoint from the URL {:#?}",
+                err
+            );
+            None
+        }
+    }
+}
+
+/// `value` if it is an http(s) URL on one of the `LOOPBACK_HOSTS`, with any
+/// port and path
+pub fn parse_endpoint(value: &str) -> Option<String> {
+    let value = value.trim();
+    let rest = value
+        .strip_prefix("http://")
+        .or_else(|| value.strip_prefix("https://"))?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && !host.ends_with(':') => (host, port),
+        _ => (authority, ""),
+    };
+
+    (LOOPBACK_HOSTS.contains(&host.to_ascii_lowercase().as_str())
+        && port.chars().all(|digit| digit.is_ascii_digit()))
+    .then(|| value.to_string())
+}
+
+/// Sends batches of runs to the telemetry endpoint
+pub trait Transport {
+    /// Start POSTing `body` to `url`. The receiver hears whether the endpoint
+    /// accepted it.
+    fn post(&self, url: &str, body: String) -> oneshot::Receiver<Result<()>>;
+}
+
+/// Posts with the browser's `fetch`
+pub struct FetchTransport;
+
+impl Transport for FetchTransport {
+    fn post(&self, url: &str, body: String) -> oneshot::Receiver<Result<()>> {
+        let (sender, receiver) = oneshot::channel();
+        let url = url.to_string();
+        browser::spawn_local(async move {
+            let sent = browser::fetch_post_json(&url, &body).await.map(|_| ());
+            // Nobody is waiting for the result if telemetry was dropped
+            let _ = sender.send(sent);
+        });
+
+        receiver
+    }
+}
+
+/// What happened in one run, as sent to the endpoint
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RunReport {
+    /// Segment of the obstacle that knocked the boy out, if one did
+    pub death_segment: Option<String>,
+    pub events: Vec<ReportedEvent>,
+    /// Length of the run, i.e. how long the boy stayed up unless he finished
+    /// a level
+    pub frames: u32,
+    pub jumps: u32,
+    pub meters: i32,
+    /// "daily", "endless" or "level" and the level's name
+    pub mode: String,
+    pub score: i32,
+    pub seed: u64,
+    pub slides: u32,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ReportedEvent {
+    /// Frames since the run started
+    pub frame: u32,
+    pub kind: GameEventKind,
+    /// Id of the obstacle the event was about, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+/// The body of each request
+#[derive(Serialize)]
+struct Batch<'a> {
+    runs: Vec<&'a RunReport>,
+}
+
+/// What is kept in `Storage`: the runs waiting to be sent and where to
+#[derive(Deserialize, Serialize)]
+struct Buffered<R> {
+    endpoint: String,
+    runs: R,
+}
+
+/// The run being played, until it is reported
+#[derive(Debug, Default)]
+struct RunLog {
+    death_segment: Option<String>,
+    events: Vec<ReportedEvent>,
+    jumps: u32,
+    slides: u32,
+    /// Frame the run started at
+    started: u32,
+}
+
+/// A request waiting for its answer
+struct Sending {
+    receiver: oneshot::Receiver<Result<()>>,
+    /// How many runs from the front of the buffer it carries
+    runs: usize,
+}
+
+/// Opt-in reports of each run for tuning difficulty, POSTed as JSON to an
+/// endpoint. Runs are buffered in `Storage` until the endpoint accepts them,
+/// so they survive being offline or reloading the page, and failed sends are
+/// retried with a growing delay.
+pub struct Telemetry {
+    /// Runs waiting to be sent, oldest first
+    buffer: VecDeque<RunReport>,
+    /// Where runs are sent. Nothing is recorded without one.
+    endpoint: Option<String>,
+    /// Failed sends in a row
+    failures: u32,
+    frame: u32,
+    /// No sends before this frame, after a failure
+    retry_at: u32,
+    run: Option<RunLog>,
+    sending: Option<Sending>,
+    storage: Box<dyn Storage>,
+    transport: Box<dyn Transport>,
+}
+
+impl Telemetry {
+    /// Load the runs that weren't sent yet to `endpoint`. Missing or
+    /// unreadable runs start out empty, and runs buffered for another
+    /// endpoint are dropped rather than sent to this one.
+    pub fn load(
+        endpoint: Option<String>,
+        storage: Box<dyn Storage>,
+        transport: Box<dyn Transport>,
+    ) -> Self {
+        let stored = endpoint.as_ref().and_then(|_| storage.get(STORAGE_KEY));
+        let buffered = stored.and_then(|json| {
+            match serde_json::from_str::<Buffered<VecDeque<RunReport>>>(&json) {
+                Ok(buffered) => Some(buffered),
+                Err(err) => {
+                    error!("Ignoring unreadable telemetry {:#?}", err);
+                    None
+                }
+            }
+        });
+        let for_another_endpoint = buffered
+            .as_ref()
+            .is_some_and(|buffered| Some(&buffered.endpoint) != endpoint.as_ref());
+
+        let mut telemetry = Telemetry {
+            buffer: buffered
+                .filter(|_| !for_another_endpoint)
+                .map(|buffered| buffered.runs)
+                .unwrap_or_default(),
+            endpoint,
+            failures: 0,
+            frame: 0,
+            retry_at: 0,
+            run: None,
+            sending: None,
+            storage,
+            transport,
+        };
+        if for_another_endpoint {
+            error!("Dropping telemetry buffered for another endpoint");
+            telemetry.save();
+        }
+
+        telemetry
+    }
+
+    /// Report the run that just ended, with its final `stats` and length in
+    /// `frames`
+    pub fn end_run(&mut self, stats: &RunStats, frames: u32, seed: u64, mode: &str) {
+        let Some(run) = self.run.take() else {
+            return;
+        };
+
+        if self.buffer.len() == MAX_BUFFERED {
+            self.buffer.pop_front();
+            if let Some(sending) = &mut self.sending {
+                sending.runs = sending.runs.saturating_sub(1);
+            }
+        }
+        self.buffer.push_back(RunReport {
+            death_segment: run.death_segment,
+            events: run.events,
+            frames,
+            jumps: run.jumps,
+            meters: stats.meters,
+            mode: mode.to_string(),
+            score: stats.score,
+            seed,
+            slides: run.slides,
+        });
+        self.save();
+    }
+
+    /// Call every frame. Sends the oldest buffered runs when nothing is being
+    /// sent and no retry is pending.
+    pub fn update(&mut self) {
+        if self.endpoint.is_none() {
+            return;
+        }
+        self.frame += 1;
+
+        if self.poll_sending() || self.buffer.is_empty() || self.frame < self.retry_at {
+            return;
+        }
+        self.send();
+    }
+
+    /// Check on the request being sent, if any. Returns true if it hasn't
+    /// been answered yet.
+    fn poll_sending(&mut self) -> bool {
+        let Some(sending) = &mut self.sending else {
+            return false;
+        };
+        let sent = match sending.receiver.try_recv() {
+            Ok(None) => return true,
+            Ok(Some(sent)) => sent,
+            Err(_) => Err(anyhow!("The request was dropped")),
+        };
+        let runs = sending.runs;
+        self.sending = None;
+
+        match sent {
+            Ok(()) => {
+                self.failures = 0;
+                self.buffer.drain(..runs.min(self.buffer.len()));
+                self.save();
+            }
+            Err(err) => {
+                self.failures += 1;
+                let wait = retry_frames(self.failures);
+                self.retry_at = self.frame + wait;
+                error!(
+                    "Could not send telemetry, trying again in {}s {:#?}",
+                    wait / 60,
+                    err
+                );
+            }
+        }
+
+        false
+    }
+
+    fn save(&mut self) {
+        let Some(endpoint) = &self.endpoint else {
+            return;
+        };
+        let buffered = Buffered {
+            endpoint: endpoint.clone(),
+            runs: &self.buffer,
+        };
+        let saved = serde_json::to_string(&buffered)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| self.storage.set(STORAGE_KEY, &json));
+        if let Err(err) = saved {
+            error!("Could not save telemetry {:#?}", err);
+        }
+    }
+
+    fn send(&mut self) {
+        let Some(endpoint) = &self.endpoint else {
+            return;
+        };
+        let runs = self.buffer.len().min(BATCH_SIZE);
+        let batch = Batch {
+            runs: self.buffer.iter().take(runs).collect(),
+        };
+
+        match serde_json::to_string(&batch) {
+            Ok(body) => {
+                self.sending = Some(Sending {
+                    receiver: self.transport.post(endpoint, body),
+                    runs,
+                })
+            }
+            Err(err) => error!("Could not serialize telemetry {:#?}", err),
+        }
+    }
+}
+
+/// Frames to wait after `failures` failed sends in a row
+fn retry_frames(failures: u32) -> u32 {
+    let doublings = failures.saturating_sub(1).min(16);
+
+    RETRY_FRAMES
+        .saturating_mul(1 << doublings)
+        .min(MAX_RETRY_FRAMES)
+}
+
+impl EventSubscriber for Telemetry {
+    fn name(&self) -> String {
+        "Telemetry".to_string()
+    }
+
+    fn process_event(&mut self, event: &GameEvent) {
+        if let GameEvent::GameStarted = event {
+            self.run = Some(RunLog {
+                started: self.frame,
+                ..RunLog::default()
+            });
+            return;
+        }
+        let Some(run) = &mut self.run else {
+            return;
+        };
+
+        match event {
+            GameEvent::BoyHitsObstacle { segment, .. } if run.death_segment.is_none() => {
+                run.death_segment = Some(segment.clone());
+            }
+            GameEvent::BoyJumped => run.jumps += 1,
+            GameEvent::BoySlid => run.slides += 1,
+            _ => (),
+        }
+        if run.events.len() < MAX_RUN_EVENTS {
+            run.events.push(ReportedEvent {
+                frame: self.frame - run.started,
+                kind: event.kind(),
+                target: event.target().map(str::to_string),
+            });
+        }
+    }
+
+    /// Nothing unless the player opted in
+    fn subscriptions(&self) -> Vec<Subscription> {
+        if self.endpoint.is_none() {
+            return vec![];
+        }
+
+        std::iter::once(GameEventKind::GameStarted)
+            .chain(REPORTED_EVENTS)
+            .map(Subscription::to)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::storage::MemoryStorage;
+    use std::{cell::RefCell, rc::Rc};
+
+    const ENDPOINT: &str = "http://localhost:8080/runs";
+
+    /// A stand-in for the endpoint, answering each request with the next of
+    /// its answers (and failing once they run out) and keeping the bodies it
+    /// was sent. Answers arrive as soon as `post` returns.
+    #[derive(Clone, Default)]
+    struct StandIn {
+        accepts: Rc<RefCell<VecDeque<bool>>>,
+        received: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl StandIn {
+        fn answering(accepts: impl IntoIterator<Item = bool>) -> Self {
+            StandIn {
+                accepts: Rc::new(RefCell::new(accepts.into_iter().collect())),
+                ..StandIn::default()
+            }
+        }
+
+        fn received(&self) -> Vec<serde_json::Value> {
+            self.received
+                .borrow()
+                .iter()
+                .map(|body| serde_json::from_str(body).unwrap())
+                .collect()
+        }
+    }
+
+    impl Transport for StandIn {
+        fn post(&self, url: &str, body: String) -> oneshot::Receiver<Result<()>> {
+            assert_eq!(url, ENDPOINT);
+            self.received.borrow_mut().push(body);
+            let answer = match self.accepts.borrow_mut().pop_front() {
+                Some(true) => Ok(()),
+                _ => Err(anyhow!("{url} responded 503")),
+            };
+            let (sender, receiver) = oneshot::channel();
+            sender.send(answer).unwrap();
+
+            receiver
+        }
+    }
+
+    fn telemetry(endpoint: Option<&str>, stand_in: &StandIn) -> Telemetry {
+        Telemetry::load(
+            endpoint.map(str::to_string),
+            Box::<MemoryStorage>::default(),
+            Box::new(stand_in.clone()),
+        )
+    }
+
+    fn stats(meters: i32) -> RunStats {
+        RunStats {
+            bonus: 5,
+            bonus_events: 1,
+            meters,
+            score: meters + 5,
+            seconds: 2,
+        }
+    }
+
+    fn play_run(telemetry: &mut Telemetry, seed: u64) {
+        telemetry.process_event(&GameEvent::GameStarted);
+        telemetry.process_event(&GameEvent::BoyJumped);
+        telemetry.end_run(&stats(12), 150, seed, "endless");
+    }
+
+    #[test]
+    fn runs_are_posted_in_batches() {
+        let stand_in = StandIn::answering([true]);
+        let mut telemetry = telemetry(Some(ENDPOINT), &stand_in);
+
+        telemetry.process_event(&GameEvent::GameStarted);
+        telemetry.update();
+        telemetry.process_event(&GameEvent::BoyJumped);
+        telemetry.process_event(&GameEvent::BoySlid);
+        telemetry.update();
+        telemetry.process_event(&GameEvent::BoyHitsObstacle {
+            id: "b3".to_string(),
+            segment: "stone/high".to_string(),
+        });
+        telemetry.end_run(&stats(12), 150, 42, "endless");
+        play_run(&mut telemetry, 43);
+        telemetry.update();
+        telemetry.update();
+
+        let received = stand_in.received();
+        assert_eq!(received.len(), 1);
+        assert_eq!(
+            received[0]["runs"][0],
+            serde_json::json!({
+                "death_segment": "stone/high",
+                "events": [
+                    {"frame": 1, "kind": "BoyJumped"},
+                    {"frame": 1, "kind": "BoySlid"},
+                    {"frame": 2, "kind": "BoyHitsObstacle", "target": "b3"}
+                ],
+                "frames": 150,
+                "jumps": 1,
+                "meters": 12,
+                "mode": "endless",
+                "score": 17,
+                "seed": 42,
+                "slides": 1
+            })
+        );
+        assert_eq!(received[0]["runs"][1]["seed"], 43);
+        assert!(received[0]["runs"][1]["death_segment"].is_null());
+        assert!(telemetry.buffer.is_empty());
+    }
+
+    #[test]
+    fn failed_sends_are_retried_later() {
+        let stand_in = StandIn::answering([false, true]);
+        let mut telemetry = telemetry(Some(ENDPOINT), &stand_in);
+        play_run(&mut telemetry, 7);
+
+        telemetry.update();
+        telemetry.update();
+        assert_eq!(telemetry.buffer.len(), 1);
+        (1..RETRY_FRAMES).for_each(|_| telemetry.update());
+        assert_eq!(stand_in.received().len(), 1);
+
+        telemetry.update();
+        telemetry.update();
+        let received = stand_in.received();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0], received[1]);
+        assert!(telemetry.buffer.is_empty());
+
+        assert_eq!(retry_frames(1), RETRY_FRAMES);
+        assert_eq!(retry_frames(2), 2 * RETRY_FRAMES);
+        assert_eq!(retry_frames(40), MAX_RETRY_FRAMES);
+    }
+
+    #[test]
+    fn unsent_runs_survive_reloading_and_the_oldest_are_dropped() {
+        let unreachable = StandIn::default();
+        let mut telemetry = telemetry(Some(ENDPOINT), &unreachable);
+        for seed in 0..MAX_BUFFERED as u64 + 2 {
+            play_run(&mut telemetry, seed);
+        }
+        telemetry.update();
+        telemetry.update();
+        assert_eq!(telemetry.failures, 1);
+
+        let mut storage = MemoryStorage::default();
+        storage
+            .set(STORAGE_KEY, &telemetry.storage.get(STORAGE_KEY).unwrap())
+            .unwrap();
+        let reloaded = Telemetry::load(
+            Some(ENDPOINT.to_string()),
+            Box::new(storage),
+            Box::new(unreachable),
+        );
+
+        assert_eq!(reloaded.buffer.len(), MAX_BUFFERED);
+        assert_eq!(reloaded.buffer[0].seed, 2);
+    }
+
+    #[test]
+    fn nothing_is_recorded_without_opting_in() {
+        let stand_in = StandIn::default();
+        let mut telemetry = telemetry(None, &stand_in);
+        assert!(telemetry.subscriptions().is_empty());
+
+        telemetry.end_run(&stats(12), 150, 1, "daily");
+        telemetry.update();
+
+        assert!(telemetry.buffer.is_empty());
+        assert!(stand_in.received().is_empty());
+    }
+
+    #[test]
+    fn only_endpoints_on_localhost_are_accepted() {
+        assert_eq!(
+            parse_endpoint(" http://localhost:8080/runs "),
+            Some("http://localhost:8080/runs".to_string())
+        );
+        assert!(parse_endpoint("https://127.0.0.1/runs?key=1").is_some());
+        assert!(parse_endpoint("http://[::1]:8080").is_some());
+
+        assert_eq!(parse_endpoint("localhost:8080/runs"), None);
+        assert_eq!(parse_endpoint("https://example.com/runs"), None);
+        assert_eq!(parse_endpoint("http://localhost.example.com/runs"), None);
+        assert_eq!(parse_endpoint("http://localhost@example.com/runs"), None);
+        assert_eq!(parse_endpoint("http://127.0.0.1:80@example.com/runs"), None);
+    }
+
+    #[test]
+    fn runs_buffered_for_another_endpoint_are_dropped() {
+        let mut telemetry = telemetry(Some(ENDPOINT), &StandIn::default());
+        play_run(&mut telemetry, 1);
+        let saved = telemetry.storage.get(STORAGE_KEY).unwrap();
+        let reload = |endpoint: Option<&str>| {
+            let mut storage = MemoryStorage::default();
+            storage.set(STORAGE_KEY, &saved).unwrap();
+            Telemetry::load(
+                endpoint.map(str::to_string),
+                Box::new(storage),
+                Box::new(StandIn::default()),
+            )
+        };
+
+        assert_eq!(reload(Some(ENDPOINT)).buffer.len(), 1);
+        assert!(reload(None).buffer.is_empty());
+        let moved = reload(Some("http://localhost:9090/runs"));
+        assert!(moved.buffer.is_empty());
+        assert!(!moved
+            .storage
+            .get(STORAGE_KEY)
+            .unwrap()
+            .contains("\"seed\":1"));
+    }
+
+    /// Integration tests against a stand-in HTTP server on localhost. They
+    /// POST over a plain TCP connection, since `FetchTransport` needs a
+    /// browser.
+    mod http {
+        use super::*;
+        use std::{
+            io::{BufRead, BufReader, Read, Write},
+            net::{TcpListener, TcpStream},
+            sync::{Arc, Mutex},
+            thread,
+        };
+
+        /// POSTs and waits for the answer, so every send has been answered by
+        /// the time `post` returns
+        struct HttpTransport;
+
+        impl Transport for HttpTransport {
+            fn post(&self, url: &str, body: String) -> oneshot::Receiver<Result<()>> {
+                let (sender, receiver) = oneshot::channel();
+                sender.send(http_post(url, &body)).unwrap();
+
+                receiver
+            }
+        }
+
+        fn http_post(url: &str, body: &str) -> Result<()> {
+            let (host, path) = url
+                .strip_prefix("http://")
+                .and_then(|rest| rest.split_once('/'))
+                .ok_or_else(|| anyhow!("Not a plain http URL {url}"))?;
+            let mut stream = TcpStream::connect(host)?;
+            write!(
+                stream,
+                "POST /{path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )?;
+            let mut response = String::new();
+            stream.read_to_string(&mut response)?;
+
+            match response.split_whitespace().nth(1) {
+                Some(status) if status.starts_with('2') => Ok(()),
+                _ => Err(anyhow!("{url} responded {response}")),
+            }
+        }
+
+        /// The endpoint on localhost, answering each request with the next of
+        /// `statuses` and keeping the bodies it was sent
+        struct HttpStandIn {
+            received: Arc<Mutex<Vec<String>>>,
+            url: String,
+        }
+
+        impl HttpStandIn {
+            fn start(statuses: Vec<u16>) -> Self {
+                let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+                let url = format!("http://{}/runs", listener.local_addr().unwrap());
+                let received = Arc::new(Mutex::new(Vec::new()));
+                let bodies = Arc::clone(&received);
+                thread::spawn(move || {
+                    for status in statuses {
+                        let (mut stream, _) = listener.accept().unwrap();
+                        bodies.lock().unwrap().push(read_body(&stream));
+                        write!(
+                            stream,
+                            "HTTP/1.1 {status} Stand-in\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        )
+                        .unwrap();
+                    }
+                });
+
+                HttpStandIn { received, url }
+            }
+
+            fn received(&self) -> Vec<serde_json::Value> {
+                self.received
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|body| serde_json::from_str(body).unwrap())
+                    .collect()
+            }
+
+            fn telemetry(&self, storage: MemoryStorage) -> Telemetry {
+                Telemetry::load(
+                    Some(self.url.clone()),
+                    Box::new(storage),
+                    Box::new(HttpTransport),
+                )
+            }
+        }
+
+        fn read_body(stream: &TcpStream) -> String {
+            let mut reader = BufReader::new(stream);
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            String::from_utf8(body).unwrap()
+        }
+
+        #[test]
+        fn posts_each_batch_as_json() {
+            let stand_in = HttpStandIn::start(vec![200]);
+            let mut telemetry = stand_in.telemetry(MemoryStorage::default());
+            play_run(&mut telemetry, 42);
+            play_run(&mut telemetry, 43);
+
+            telemetry.update();
+            telemetry.update();
+
+            let received = stand_in.received();
+            assert_eq!(received.len(), 1);
+            assert_eq!(
+                received[0]["runs"][0],
+                serde_json::json!({
+                    "death_segment": null,
+                    "events": [{"frame": 0, "kind": "BoyJumped"}],
+                    "frames": 150,
+                    "jumps": 1,
+                    "meters": 12,
+                    "mode": "endless",
+                    "score": 17,
+                    "seed": 42,
+                    "slides": 0
+                })
+            );
+            assert_eq!(received[0]["runs"][1]["seed"], 43);
+            assert!(telemetry.buffer.is_empty());
+        }
+
+        #[test]
+        fn runs_kept_while_the_endpoint_fails_are_sent_after_reloading() {
+            let stand_in = HttpStandIn::start(vec![503, 200]);
+            let mut telemetry = stand_in.telemetry(MemoryStorage::default());
+            play_run(&mut telemetry, 7);
+
+            telemetry.update();
+            telemetry.update();
+            assert_eq!(telemetry.failures, 1);
+            assert_eq!(telemetry.buffer.len(), 1);
+
+            let mut storage = MemoryStorage::default();
+            storage
+                .set(STORAGE_KEY, &telemetry.storage.get(STORAGE_KEY).unwrap())
+                .unwrap();
+            let mut reloaded = stand_in.telemetry(storage);
+            reloaded.update();
+            reloaded.update();
+
+            let received = stand_in.received();
+            assert_eq!(received.len(), 2);
+            assert_eq!(received[0], received[1]);
+            assert_eq!(received[1]["runs"][0]["seed"], 7);
+            assert!(reloaded.buffer.is_empty());
+        }
+    }
+}
@@ -1,59 +1,143 @@
 use super::{
+    achievements::Achievements,
+    daily_run::{self, DailyRun},
+    difficulty::{Difficulty, MAX_SPEED_BONUS},
     dog::Dog,
-    event_queue::{EventPublisher, EventQueue, EventSubscriber, GameEvent},
-    obstacles::{Obstacle, ObstacleVec},
+    editor::Editor,
+    event_bus::EventBus,
+    event_queue::{self, EventPublisher, EventQueue, GameEvent, Phase},
+    event_trace::EventTrace,
+    high_scores::HighScores,
+    levels::{Level, LevelRecords, Levels},
+    obstacles::{Obstacle, ObstacleIndex, ObstacleVec},
     red_hat_boy::RedHatBoy,
-    segments::SegmentFactory,
+    score::Score,
+    seed::{self, RunRng},
+    segments::{
+        definitions::{ObstacleSpec, SegmentDefinitions},
+        SegmentFactory,
+    },
+    sound_effects::{AudioReactions, Emitter},
+    telemetry::Telemetry,
+    RUNNING_SPEED,
+};
+use crate::{
+    browser,
+    engine::{
+        image::Image,
+        rect::{Point, Rect},
+        renderer::Renderer,
+        sprite_sheet::SpriteSheet,
+        storage,
+    },
 };
-use crate::engine::{image::Image, rect::Point, renderer::Renderer, sprite_sheet::SpriteSheet};
 use std::{
     cell::{RefCell, RefMut},
-    collections::HashSet,
     rc::Rc,
 };
 use web_sys::HtmlImageElement;
 
-const OBSTACLE_BUFFER: i16 = 20;
+/// How far either side of the boy and the dog obstacles are checked against
+/// them. Twice the most they and the course can move apart in a frame (the
+/// course scrolling at its fastest while the dog runs the other way), so an
+/// obstacle is checked once more after they have left it, which is when
+/// barriers and the finish line see the boy has passed them.
+const NEARBY: i16 = 2 * (2 * RUNNING_SPEED + MAX_SPEED_BONUS);
+
+/// Where a run's seed comes from
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RunMode {
+    /// Seeded from today's UTC date, so everyone races the same course
+    Daily,
+    /// Seeded randomly, unless a seed was fixed
+    Endless,
+    /// The level at this index of `Levels`: its segments in order, then the
+    /// finish line
+    Level(usize),
+}
 
 pub struct Walk {
     pub backgrounds: [Image; 2],
-    pub boy: RedHatBoy,
+    boy: Rc<RefCell<RedHatBoy>>,
+    pub daily_run: DailyRun,
     pub event_publisher: EventPublisher,
-    pub obstacles: ObstacleVec,
+    pub high_scores: HighScores,
+    pub level_records: LevelRecords,
+    pub obstacles: ObstacleIndex,
     pub timeline: i16,
 
+    achievements: Rc<RefCell<Achievements>>,
+    audio_reactions: Rc<RefCell<AudioReactions>>,
+    difficulty: Difficulty,
     dog: Rc<RefCell<Dog>>,
-    event_subscribers: Vec<Subscriber>,
+    /// The segment being edited, kept while it is played
+    editor: Option<Editor>,
+    event_bus: EventBus,
+    event_trace: EventTrace,
     events: EventQueue,
+    level_finished: bool,
+    levels: Levels,
+    mode: RunMode,
+    /// Index into the level's segments of the next one to build
+    next_level_segment: usize,
+    rng: RunRng,
+    score: Rc<RefCell<Score>>,
+    seed: u64,
     segment_factory: SegmentFactory,
     stone: HtmlImageElement,
+    telemetry: Rc<RefCell<Telemetry>>,
 }
 
 impl Walk {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
+        achievements: Achievements,
+        audio_reactions: AudioReactions,
         background: HtmlImageElement,
         boy: RedHatBoy,
+        difficulty: Difficulty,
         dog: Dog,
         event_publisher: EventPublisher,
         events: EventQueue,
+        levels: Levels,
         stone: HtmlImageElement,
+        segment_definitions: SegmentDefinitions,
         segment_tiles: SpriteSheet,
+        telemetry: Telemetry,
     ) -> Self {
-        let mut segment_factory =
-            SegmentFactory::new(segment_tiles, stone.clone(), event_publisher.clone());
-        let starting_obstacles = segment_factory.first();
+        let mut segment_factory = SegmentFactory::new(
+            segment_definitions,
+            segment_tiles,
+            stone.clone(),
+            event_publisher.clone(),
+        );
+        let mode = RunMode::Endless;
+        let starting_obstacles = starting_obstacles(&mut segment_factory, &levels, mode);
         let timeline = rightmost(&starting_obstacles);
 
-        let mut event_subscribers: Vec<Subscriber> = Vec::new();
+        let mut event_bus = EventBus::new();
+        let boy = Rc::new(RefCell::new(boy));
         let dog = Rc::new(RefCell::new(dog));
-        event_subscribers.push(Subscriber::Dog(Rc::clone(&dog)));
+        let audio_reactions = Rc::new(RefCell::new(audio_reactions));
+        let achievements = Rc::new(RefCell::new(achievements));
+        let score = Rc::new(RefCell::new(Score::new()));
+        let telemetry = Rc::new(RefCell::new(telemetry));
+        event_bus.subscribe(&boy);
+        event_bus.subscribe(&dog);
+        event_bus.subscribe(&audio_reactions);
+        event_bus.subscribe(&achievements);
+        event_bus.subscribe(&score);
+        event_bus.subscribe(&telemetry);
         for obstacle in &starting_obstacles {
-            event_subscribers.push(Subscriber::Obstacle(Rc::clone(obstacle)));
+            event_bus.subscribe(obstacle);
         }
 
         let background_width = background.width() as i16;
+        let seed = run_seed(mode);
 
         Walk {
+            achievements,
+            audio_reactions,
             backgrounds: [
                 Image::new(background.clone(), Point { x: 0, y: 0 }),
                 Image::new(
@@ -65,138 +149,405 @@ impl Walk {
                 ),
             ],
             boy,
+            daily_run: DailyRun::load(storage::browser_storage()),
+            difficulty,
             dog,
+            editor: None,
             events,
             event_publisher,
-            event_subscribers,
-            obstacles: starting_obstacles,
+            event_bus,
+            event_trace: EventTrace::default(),
+            high_scores: HighScores::load(storage::browser_storage()),
+            level_finished: false,
+            level_records: LevelRecords::load(storage::browser_storage()),
+            levels,
+            mode,
+            next_level_segment: 1,
+            obstacles: indexed(starting_obstacles),
+            rng: seed::run_rng(seed),
+            score,
+            seed,
             segment_factory,
             stone,
+            telemetry,
             timeline,
         }
     }
 
-    pub fn reset(mut walk: Self) -> Self {
+    pub fn reset(walk: Self) -> Self {
         let mut segment_factory = walk.segment_factory;
-        let starting_obstacles = segment_factory.first();
+        let starting_obstacles = starting_obstacles(&mut segment_factory, &walk.levels, walk.mode);
         let timeline = rightmost(&starting_obstacles);
 
-        walk.event_subscribers.clear();
-
-        let dog = if let Ok(dog) = Rc::try_unwrap(walk.dog) {
-            Rc::new(RefCell::new(Dog::reset(dog.into_inner())))
-        } else {
-            panic!("Unable to take dog ownership!");
-        };
-
-        let mut event_subscribers: Vec<Subscriber> = Vec::new();
-        event_subscribers.push(Subscriber::Dog(Rc::clone(&dog)));
+        // Reset in place rather than rebuilt, so it doesn't matter who else
+        // holds them
+        walk.boy.borrow_mut().reset();
+        walk.dog.borrow_mut().reset();
+
+        let score = Rc::new(RefCell::new(Score::new()));
+        // The old score and obstacles unsubscribed themselves when dropped.
+        // The boy, dog, achievements, audio reactions and telemetry carry
+        // over, so they are still subscribed.
+        let mut event_bus = walk.event_bus;
+        event_bus.subscribe(&score);
         for obstacle in &starting_obstacles {
-            event_subscribers.push(Subscriber::Obstacle(Rc::clone(obstacle)));
+            event_bus.subscribe(obstacle);
         }
 
+        let seed = run_seed(walk.mode);
+
         Walk {
+            achievements: walk.achievements,
+            audio_reactions: walk.audio_reactions,
             backgrounds: walk.backgrounds,
-            boy: RedHatBoy::reset(walk.boy),
-            dog,
+            boy: walk.boy,
+            daily_run: walk.daily_run,
+            difficulty: walk.difficulty,
+            dog: walk.dog,
+            editor: walk.editor,
             events: walk.events,
             event_publisher: walk.event_publisher,
-            event_subscribers,
-            obstacles: starting_obstacles,
+            event_bus,
+            event_trace: walk.event_trace,
+            high_scores: walk.high_scores,
+            level_finished: false,
+            level_records: walk.level_records,
+            levels: walk.levels,
+            mode: walk.mode,
+            next_level_segment: 1,
+            obstacles: indexed(starting_obstacles),
+            rng: seed::run_rng(seed),
+            score,
+            seed,
             segment_factory,
             stone: walk.stone,
+            telemetry: walk.telemetry,
             timeline,
         }
     }
 
+    /// Build `obstacles` from the editor, `offset_x` pixels along, without
+    /// adding them to the walk
+    pub fn build_obstacles(&mut self, obstacles: &[ObstacleSpec], offset_x: i16) -> ObstacleVec {
+        self.segment_factory.build(obstacles, offset_x)
+    }
+
+    /// Keep `editor` until the editor is next opened
+    pub fn close_editor(&mut self, editor: Editor) {
+        self.editor = Some(editor);
+    }
+
+    pub fn achievements(&self) -> RefMut<Achievements> {
+        self.achievements.as_ref().borrow_mut()
+    }
+
+    pub fn boy(&self) -> RefMut<RedHatBoy> {
+        self.boy.as_ref().borrow_mut()
+    }
+
     pub fn dog(&self) -> RefMut<Dog> {
         self.dog.as_ref().borrow_mut()
     }
 
     pub fn draw(&self, renderer: &Renderer) {
         self.backgrounds.iter().for_each(|b| b.draw(renderer));
-        self.boy.draw(renderer);
+        self.boy.borrow().draw(renderer);
         self.dog().draw(renderer);
         self.obstacles
             .iter()
             .for_each(|o| o.borrow().draw(renderer));
+        self.score.borrow().draw(renderer);
+        self.achievements.borrow().draw(renderer);
     }
 
+    /// Drop obstacles that have scrolled off the left of the screen, which
+    /// also unsubscribes them
     pub fn drop_surpassed_obstacles(&mut self) {
-        let to_drop: HashSet<String> = self
-            .obstacles
-            .iter()
-            .filter(|o| o.borrow().right() <= 0)
-            .map(|o| o.borrow().id().to_string())
-            .collect();
+        let dropped = self.obstacles.remove_left_of(0);
 
-        if to_drop.len() == 0 {
+        if dropped == 0 {
             return;
         }
 
-        self.obstacles
-            .retain(|o| !to_drop.contains(&o.borrow().id()));
-        self.event_subscribers
-            .retain(|s| !to_drop.contains(&s.name()));
-
         log!(
-            "Dropped {} obstacles left behind. Total={} Subscribers={}",
-            to_drop.len(),
+            "Dropped {dropped} obstacles left behind. Total={} Subscribers={}",
             self.obstacles.len(),
-            self.event_subscribers.len()
+            self.event_bus.subscriber_count()
         );
     }
 
+    /// Add the next segment to the course: picked at random, or the level's
+    /// next segment and then its finish line. Nothing once a level's course
+    /// is complete.
     pub fn generate_next_segment(&mut self) {
-        let offset_x = self.timeline + OBSTACLE_BUFFER;
-        let mut next_obstacles = self.segment_factory.random(offset_x);
+        let level = self.difficulty_level();
+        let offset_x = self.timeline + self.difficulty.obstacle_buffer(level);
+        let next_obstacles = match self.mode {
+            RunMode::Level(current) => {
+                let segments = self
+                    .levels
+                    .get(current)
+                    .map_or(&[][..], |level| level.segments.as_slice());
+                let next_obstacles = match segments.get(self.next_level_segment) {
+                    Some(name) => self.segment_factory.named(name, offset_x),
+                    None if self.next_level_segment == segments.len() => {
+                        self.segment_factory.finish_line(offset_x)
+                    }
+                    None => return,
+                };
+                self.next_level_segment += 1;
+
+                next_obstacles
+            }
+            RunMode::Daily | RunMode::Endless => self.segment_factory.random(
+                offset_x,
+                level,
+                |segment| self.difficulty.segment_weight(segment, level),
+                &mut self.rng,
+            ),
+        };
 
         self.timeline = rightmost(&next_obstacles);
 
         for obstacle in &next_obstacles {
-            self.event_subscribers
-                .push(Subscriber::Obstacle(Rc::clone(obstacle)));
+            self.event_bus.subscribe(obstacle);
         }
         let to_add = next_obstacles.len();
 
-        self.obstacles.append(&mut next_obstacles);
+        self.obstacles.extend(with_extents(next_obstacles));
 
         log!(
             "Appended {to_add} obstacles. Total={} Subscribers={}",
             self.obstacles.len(),
-            self.event_subscribers.len()
+            self.event_bus.subscriber_count()
         );
     }
 
+    /// Scroll the obstacles by `x` and check those near the boy against him.
+    /// He reacts to what each one publishes before the next one is checked.
+    pub fn move_obstacles(&mut self, x: i16) {
+        self.obstacles.scroll(x);
+        for obstacle in self.obstacles.iter() {
+            obstacle.borrow_mut().move_horizontally(x);
+        }
+
+        let (left, right) = nearby(&self.boy.borrow().bounding_box());
+        for obstacle in self.obstacles.near(left, right) {
+            obstacle.borrow().check_intersection(&self.boy.borrow(), x);
+            dispatch_events(
+                &self.events,
+                &self.event_bus,
+                &mut self.event_trace,
+                &mut self.level_finished,
+                Phase::Immediate,
+            );
+        }
+    }
+
+    /// The most recently dispatched events
+    pub fn event_trace(&self) -> &EventTrace {
+        &self.event_trace
+    }
+
     pub fn knocked_out(&self) -> bool {
-        self.boy.knocked_out()
+        self.boy.borrow().knocked_out()
+    }
+
+    /// The level being played, with its index, if the mode is `RunMode::Level`
+    pub fn level(&self) -> Option<(usize, &Level)> {
+        match self.mode {
+            RunMode::Level(level) => self.levels.get(level).map(|found| (level, found)),
+            RunMode::Daily | RunMode::Endless => None,
+        }
+    }
+
+    /// True once the boy has crossed the level's finish line
+    pub fn level_finished(&self) -> bool {
+        self.level_finished
+    }
+
+    pub fn levels(&self) -> &Levels {
+        &self.levels
+    }
+
+    pub fn mode(&self) -> RunMode {
+        self.mode
+    }
+
+    /// The mode for reports, e.g. "endless" or "level First Steps"
+    pub fn mode_name(&self) -> String {
+        match (self.mode, self.level()) {
+            (RunMode::Daily, _) => "daily".to_string(),
+            (RunMode::Endless, _) => "endless".to_string(),
+            (RunMode::Level(_), level) => {
+                format!("level {}", level.map_or("", |(_, level)| &level.name))
+            }
+        }
+    }
+
+    /// The editor as it was last closed, or a new one with no obstacles
+    pub fn open_editor(&mut self) -> Editor {
+        self.editor.take().unwrap_or_else(|| {
+            Editor::new(
+                self.segment_factory.definitions().clone(),
+                (self.stone.width() as i16, self.stone.height() as i16),
+            )
+        })
+    }
+
+    /// Replace the course ahead with `obstacles`, e.g. a segment from the
+    /// editor. Picked segments follow once they are passed.
+    pub fn play_from(&mut self, obstacles: ObstacleVec) {
+        for obstacle in &obstacles {
+            self.event_bus.subscribe(obstacle);
+        }
+
+        self.timeline = rightmost(&obstacles);
+        self.obstacles = indexed(obstacles);
+    }
+
+    /// Switch to `mode` before the run starts, with a new course to match
+    pub fn with_mode(mut walk: Self, mode: RunMode) -> Self {
+        if mode == walk.mode {
+            return walk;
+        }
+
+        walk.mode = mode;
+        Walk::reset(walk)
+    }
+
+    /// Seed this run's course is generated from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn score(&self) -> RefMut<Score> {
+        self.score.as_ref().borrow_mut()
+    }
+
+    pub fn telemetry(&self) -> RefMut<Telemetry> {
+        self.telemetry.as_ref().borrow_mut()
+    }
+
+    /// Begin a frame, before anything in it is moved or updated, so the
+    /// events dispatched along the way are traced under it.
+    /// `WalkTheDogStateMachine::update` calls it for every state that moves
+    /// the walk.
+    pub fn start_frame(&mut self) {
+        self.event_trace.advance_frame();
+        self.events.borrow_mut().start_frame();
     }
 
     pub fn update(&mut self) {
-        self.process_events();
+        self.process_events(Phase::NextFrame);
         self.dog().update();
         self.navigate_obstacles();
+        self.track_audio_emitters();
+        self.process_events(Phase::EndOfUpdate);
+        self.achievements
+            .borrow_mut()
+            .track(&self.score.borrow().stats());
     }
 
+    /// Scroll speed, faster than the boy's own walking speed as the run gets
+    /// harder. Stands still once the boy does.
     pub fn velocity(&self) -> i16 {
-        -self.boy.walking_speed()
+        match self.boy.borrow().walking_speed() {
+            0 => 0,
+            speed => -(speed + self.difficulty.speed_bonus(self.difficulty_level())),
+        }
+    }
+
+    fn difficulty_level(&self) -> f32 {
+        self.difficulty.level(self.score.borrow().meters())
     }
 
     fn navigate_obstacles(&mut self) {
-        for obstacle in self.obstacles.iter() {
+        let (left, right) = nearby(&self.dog.borrow().bounding_box());
+        for obstacle in self.obstacles.near(left, right) {
             obstacle.as_ref().borrow_mut().navigate(&self.dog.borrow());
         }
     }
 
-    fn process_events(&mut self) {
-        while let Some(event) = self.events.as_ref().borrow_mut().pop_front() {
-            for s in self.event_subscribers.iter_mut() {
-                s.process_event(&event);
-            }
+    fn track_audio_emitters(&mut self) {
+        let mut audio_reactions = self.audio_reactions.borrow_mut();
+        audio_reactions.track(Emitter::Boy, &self.boy.borrow().bounding_box());
+        audio_reactions.track(Emitter::Dog, &self.dog().bounding_box());
+    }
+
+    fn process_events(&mut self, phase: Phase) {
+        dispatch_events(
+            &self.events,
+            &self.event_bus,
+            &mut self.event_trace,
+            &mut self.level_finished,
+            phase,
+        );
+    }
+}
+
+/// Dispatch the events queued for `phase` and trace them, noting when the
+/// level is finished
+fn dispatch_events(
+    events: &EventQueue,
+    event_bus: &EventBus,
+    event_trace: &mut EventTrace,
+    level_finished: &mut bool,
+    phase: Phase,
+) {
+    event_queue::dispatch_phase(events, phase, |published| {
+        if matches!(published.event, GameEvent::LevelFinished) {
+            *level_finished = true;
+        }
+        let deliveries = event_bus.dispatch(&published.event);
+        if cfg!(debug_assertions) {
+            event_trace.record(published, deliveries);
         }
+    });
+}
+
+fn run_seed(mode: RunMode) -> u64 {
+    match mode {
+        RunMode::Daily => daily_run::daily_seed(&browser::today()),
+        RunMode::Endless | RunMode::Level(_) => seed::next_seed(),
+    }
+}
+
+/// The first segment of the course for `mode`
+fn starting_obstacles(
+    segment_factory: &mut SegmentFactory,
+    levels: &Levels,
+    mode: RunMode,
+) -> ObstacleVec {
+    let first_level_segment = match mode {
+        RunMode::Level(level) => levels.get(level).and_then(|level| level.segments.first()),
+        RunMode::Daily | RunMode::Endless => None,
+    };
+
+    match first_level_segment {
+        Some(name) => segment_factory.named(name, 0),
+        None => segment_factory.first(),
     }
 }
 
+fn indexed(obstacles: ObstacleVec) -> ObstacleIndex {
+    with_extents(obstacles).collect()
+}
+
+/// The span of the screen within `NEARBY` of `bounding_box`
+fn nearby(bounding_box: &Rect) -> (i16, i16) {
+    (bounding_box.left() - NEARBY, bounding_box.right() + NEARBY)
+}
+
+fn with_extents(
+    obstacles: ObstacleVec,
+) -> impl Iterator<Item = (Rc<RefCell<Box<dyn Obstacle>>>, i16, i16)> {
+    obstacles.into_iter().map(|obstacle| {
+        let (left, right) = (obstacle.borrow().left(), obstacle.borrow().right());
+        (obstacle, left, right)
+    })
+}
+
 fn rightmost(obstacle_list: &[Rc<RefCell<Box<dyn Obstacle>>>]) -> i16 {
     obstacle_list
         .iter()
@@ -204,28 +555,3 @@ fn rightmost(obstacle_list: &[Rc<RefCell<Box<dyn Obstacle>>>]) -> i16 {
         .max_by(|x, y| x.cmp(y))
         .unwrap_or(0)
 }
-
-/*
-This is super annoying, but we seem forced to wrap Dog and Obstacle inside something
-that directly implements EventSubscriber
-*/
-enum Subscriber {
-    Dog(Rc<RefCell<Dog>>),
-    Obstacle(Rc<RefCell<Box<dyn Obstacle>>>),
-}
-
-impl EventSubscriber for Subscriber {
-    fn name(&self) -> String {
-        match self {
-            Subscriber::Dog(s) => s.borrow().name(),
-            Subscriber::Obstacle(s) => s.borrow().name(),
-        }
-    }
-
-    fn process_event(&mut self, event: &GameEvent) {
-        match self {
-            Subscriber::Dog(s) => s.borrow_mut().process_event(event),
-            Subscriber::Obstacle(s) => s.borrow_mut().process_event(event),
-        }
-    }
-}
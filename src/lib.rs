@@ -12,6 +12,7 @@ use wasm_bindgen::prelude::*;
 #[wasm_bindgen(start)]
 pub fn main_js() -> Result<(), JsValue> {
     console_error_panic_hook::set_once();
+    game::seed::fix_seed_from_url();
 
     browser::spawn_local(async move {
         let game = WalkTheDog::new();
@@ -22,3 +23,16 @@ pub fn main_js() -> Result<(), JsValue> {
 
     Ok(())
 }
+
+/// Start every run from here on (from the next new game) from `seed`, e.g.
+/// `set_seed(42n)` from JavaScript
+#[wasm_bindgen]
+pub fn set_seed(seed: u64) {
+    game::seed::fix_seed(Some(seed));
+}
+
+/// Go back to a fresh random seed for every run
+#[wasm_bindgen]
+pub fn clear_seed() {
+    game::seed::fix_seed(None);
+}
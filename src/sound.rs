@@ -2,12 +2,26 @@ use anyhow::{anyhow, Result};
 use js_sys::ArrayBuffer;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{AudioBuffer, AudioBufferSourceNode, AudioContext, AudioDestinationNode, AudioNode};
+use web_sys::{
+    AudioBuffer, AudioBufferSourceNode, AudioContext, AudioNode, GainNode, StereoPannerNode,
+};
 
 pub fn create_audio_context() -> Result<AudioContext> {
     AudioContext::new().map_err(|err| anyhow!("Could not create audio context: {:#?}", err))
 }
 
+/// Mono `AudioBuffer` holding `samples` at the context's sample rate
+pub fn create_buffer(ctx: &AudioContext, samples: &[f32]) -> Result<AudioBuffer> {
+    let buffer = ctx
+        .create_buffer(1, samples.len() as u32, ctx.sample_rate())
+        .map_err(|err| anyhow!("Could not create audio buffer {:#?}", err))?;
+    buffer
+        .copy_to_channel(samples, 0)
+        .map_err(|err| anyhow!("Could not copy samples into audio buffer {:#?}", err))?;
+
+    Ok(buffer)
+}
+
 pub async fn decode_audio_data(
     ctx: &AudioContext,
     array_buffer: ArrayBuffer,
@@ -23,7 +37,22 @@ pub async fn decode_audio_data(
 }
 
 pub fn play_sound(ctx: &AudioContext, buffer: &AudioBuffer, looping: Looping) -> Result<()> {
-    let track_source = create_track_source(ctx, buffer)?;
+    play_sound_ext(ctx, buffer, looping, 1.0, 1.0, 0.0)
+}
+
+/// Play `buffer` at the given `playback_rate` (which also shifts its pitch),
+/// `volume` (gain, where 1.0 is the buffer's own level) and stereo `pan`
+/// (-1.0 is full left, 1.0 full right)
+pub fn play_sound_ext(
+    ctx: &AudioContext,
+    buffer: &AudioBuffer,
+    looping: Looping,
+    playback_rate: f32,
+    volume: f32,
+    pan: f32,
+) -> Result<()> {
+    let track_source = create_track_source(ctx, buffer, volume, pan)?;
+    track_source.playback_rate().set_value(playback_rate);
     if matches!(looping, Looping::Yes) {
         track_source.set_loop(true)
     }
@@ -38,11 +67,8 @@ pub enum Looping {
     Yes,
 }
 
-fn connect_with_audio_node(
-    buffer_source: &AudioBufferSourceNode,
-    destination: &AudioDestinationNode,
-) -> Result<AudioNode> {
-    buffer_source
+fn connect_with_audio_node(source: &AudioNode, destination: &AudioNode) -> Result<AudioNode> {
+    source
         .connect_with_audio_node(destination)
         .map_err(|err| anyhow!("Error connecting audio source to destination {:#?}", err))
 }
@@ -52,10 +78,37 @@ fn create_buffer_source(ctx: &AudioContext) -> Result<AudioBufferSourceNode> {
         .map_err(|err| anyhow!("Error creating buffer source {:#?}", err))
 }
 
-fn create_track_source(ctx: &AudioContext, buffer: &AudioBuffer) -> Result<AudioBufferSourceNode> {
+fn create_gain(ctx: &AudioContext, volume: f32) -> Result<GainNode> {
+    let gain = ctx
+        .create_gain()
+        .map_err(|err| anyhow!("Error creating gain node {:#?}", err))?;
+    gain.gain().set_value(volume);
+
+    Ok(gain)
+}
+
+fn create_stereo_panner(ctx: &AudioContext, pan: f32) -> Result<StereoPannerNode> {
+    let panner = ctx
+        .create_stereo_panner()
+        .map_err(|err| anyhow!("Error creating stereo panner {:#?}", err))?;
+    panner.pan().set_value(pan);
+
+    Ok(panner)
+}
+
+fn create_track_source(
+    ctx: &AudioContext,
+    buffer: &AudioBuffer,
+    volume: f32,
+    pan: f32,
+) -> Result<AudioBufferSourceNode> {
     let track_source = create_buffer_source(ctx)?;
     track_source.set_buffer(Some(buffer));
-    connect_with_audio_node(&track_source, &ctx.destination())?;
+    let gain = create_gain(ctx, volume)?;
+    let panner = create_stereo_panner(ctx, pan)?;
+    connect_with_audio_node(&track_source, &gain)?;
+    connect_with_audio_node(&gain, &panner)?;
+    connect_with_audio_node(&panner, &ctx.destination())?;
 
     Ok(track_source)
 }